@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+
+use super::block::BlockType;
+
+#[derive(serde::Deserialize, Asset, TypePath)]
+pub struct BiomeDef {
+    pub identifier: String,
+    /// Where the biome sits in climate space, both roughly in `[-1, 1]`.
+    pub temperature: f32,
+    pub humidity: f32,
+    pub top: String,
+    pub filler: String,
+    pub depth: usize,
+    /// World height the biome's terrain gravitates towards.
+    #[serde(default)]
+    pub height: f32,
+    /// How quickly density falls off above `height` (and rises below it). Zero leaves the density
+    /// noise untouched.
+    #[serde(default)]
+    pub height_falloff: f32,
+    /// Added to the density noise, positive values make the biome more solid.
+    #[serde(default)]
+    pub density_offset: f32,
+    #[serde(default)]
+    pub decorations: Vec<DecorationDef>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct DecorationDef {
+    pub block: String,
+    pub on: Vec<String>,
+    pub chance: f32,
+}
+
+#[derive(Clone)]
+pub struct Biome {
+    pub climate: Vec2,
+    pub top: BlockType,
+    pub filler: BlockType,
+    pub depth: usize,
+    pub height: f32,
+    pub height_falloff: f32,
+    pub density_offset: f32,
+    pub decorations: Vec<Decoration>,
+}
+
+#[derive(Clone)]
+pub struct Decoration {
+    pub block: BlockType,
+    pub on: Vec<BlockType>,
+    pub chance: f32,
+}
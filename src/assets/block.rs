@@ -7,10 +7,47 @@ pub struct BlockDef {
     pub identifier: String,
     pub model: Option<String>,
     pub textures: HashMap<String, String>,
+    /// Whether the block stops movement, for the motion blocking heightmap.
+    #[serde(default = "default_motion_blocking")]
+    pub motion_blocking: bool,
+    /// Whether bodies overlapping the block can move up and down it.
+    #[serde(default)]
+    pub climbable: bool,
+    /// Whether the block gets a block entity to hold extra data, like a chest's contents.
+    #[serde(default)]
+    pub block_entity: bool,
+    /// Seconds it takes to break the block. Zero breaks it straight away.
+    #[serde(default = "default_hardness")]
+    pub hardness: f32,
+    /// What happens when the block is used, instead of placing against it.
+    #[serde(default)]
+    pub interaction: Option<InteractionDef>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct InteractionDef {
+    /// Identifier of a behaviour registered with `register_block_interaction`.
+    pub behaviour: String,
+    /// Handed to the behaviour as is.
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+fn default_hardness() -> f32 {
+    1.0
+}
+
+fn default_motion_blocking() -> bool {
+    true
 }
 
 pub struct Block {
     pub identifier: String,
     pub model: Option<usize>,
     pub textures: HashMap<String, usize>,
+    pub motion_blocking: bool,
+    pub climbable: bool,
+    pub block_entity: bool,
+    pub hardness: f32,
+    pub interaction: Option<InteractionDef>,
 }
@@ -1,13 +1,21 @@
+mod biome;
 mod block;
 mod model;
+mod ore;
 mod registry;
+mod structure;
+mod worldgen;
 
 use bevy::prelude::*;
 
 pub use {
+    biome::{Biome, Decoration},
     block::{Block, BlockType},
     model::Model,
+    ore::Ore,
     registry::Registry,
+    structure::Structure,
+    worldgen::{NoiseDef, PlacementDef, StageDef, WorldGenDef},
 };
 
 pub struct AssetsPlugin;
@@ -1,4 +1,4 @@
-use bevy::prelude::*;
+use bevy::{math::bounding::Aabb3d, prelude::*};
 
 use super::block::Block;
 
@@ -6,11 +6,44 @@ use super::block::Block;
 pub struct ModelDef {
     pub identifier: String,
     pub faces: Vec<Face>,
+    /// `(min, max)` corners of the boxes the model collides with, in block space. Defaults to the
+    /// bounds of its faces.
+    #[serde(default)]
+    pub collision: Option<Vec<([f32; 3], [f32; 3])>>,
+}
+
+impl ModelDef {
+    pub fn collision_boxes(&self) -> Vec<Aabb3d> {
+        if let Some(boxes) = &self.collision {
+            return boxes
+                .iter()
+                .map(|(min, max)| Aabb3d {
+                    min: Vec3::from_array(*min).into(),
+                    max: Vec3::from_array(*max).into(),
+                })
+                .collect();
+        }
+
+        let mut positions = self
+            .faces
+            .iter()
+            .flat_map(|face| &face.vertices)
+            .map(|v| Vec3::from_array(v.position));
+        let Some(first) = positions.next() else {
+            return vec![];
+        };
+        let (min, max) = positions.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+        vec![Aabb3d {
+            min: min.into(),
+            max: max.into(),
+        }]
+    }
 }
 
 pub struct Model {
     pub identifier: String,
     pub faces: Vec<Face>,
+    pub collision: Vec<Aabb3d>,
 }
 
 impl Model {
@@ -0,0 +1,28 @@
+use bevy::prelude::*;
+
+use super::block::BlockType;
+
+#[derive(serde::Deserialize, Asset, TypePath)]
+pub struct OreDef {
+    pub identifier: String,
+    pub block: String,
+    /// World heights veins may start at, inclusive.
+    pub min_height: i32,
+    pub max_height: i32,
+    /// Blocks in a single vein, at most.
+    pub vein_size: u32,
+    /// How many veins each chunk tries to start.
+    pub attempts: u32,
+    /// Blocks a vein is allowed to replace.
+    pub replaceable: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct Ore {
+    pub block: BlockType,
+    pub min_height: i32,
+    pub max_height: i32,
+    pub vein_size: u32,
+    pub attempts: u32,
+    pub replaceable: Vec<BlockType>,
+}
@@ -15,8 +15,12 @@ use bevy_common_assets::ron::RonAssetPlugin;
 use crate::screens::Screen;
 
 use super::{
+    biome::{Biome, BiomeDef, Decoration, DecorationDef},
     block::{Block, BlockDef},
     model::ModelDef,
+    ore::{Ore, OreDef},
+    structure::{ShapeDef, Structure, StructureDef},
+    worldgen::WorldGenDef,
     Model,
 };
 
@@ -27,6 +31,10 @@ impl Plugin for RegistryPlugin {
         app.add_plugins((
             RonAssetPlugin::<ModelDef>::new(&["model.ron"]),
             RonAssetPlugin::<BlockDef>::new(&["block.ron"]),
+            RonAssetPlugin::<BiomeDef>::new(&["biome.ron"]),
+            RonAssetPlugin::<StructureDef>::new(&["structure.ron"]),
+            RonAssetPlugin::<OreDef>::new(&["ore.ron"]),
+            RonAssetPlugin::<WorldGenDef>::new(&["worldgen.ron"]),
         ));
         app.add_loading_state(
             LoadingState::new(Screen::Loading)
@@ -69,6 +77,26 @@ pub struct Registry {
     block_def_handles: Vec<Handle<BlockDef>>,
     block_name_to_id: HashMap<String, usize>,
     blocks: Vec<Block>,
+
+    #[asset(path = "biomes", collection(typed))]
+    biome_def_handles: Vec<Handle<BiomeDef>>,
+    biome_name_to_id: HashMap<String, usize>,
+    biomes: Vec<Biome>,
+
+    #[asset(path = "structures", collection(typed))]
+    structure_def_handles: Vec<Handle<StructureDef>>,
+    structure_name_to_id: HashMap<String, usize>,
+    structures: Vec<Structure>,
+
+    #[asset(path = "ores", collection(typed))]
+    ore_def_handles: Vec<Handle<OreDef>>,
+    ore_name_to_id: HashMap<String, usize>,
+    ores: Vec<Ore>,
+
+    #[asset(path = "worldgen", collection(typed))]
+    world_gen_def_handles: Vec<Handle<WorldGenDef>>,
+    world_gen_name_to_id: HashMap<String, usize>,
+    world_gens: Vec<WorldGenDef>,
 }
 
 // TODO: Make these return Options
@@ -81,10 +109,18 @@ impl Registry {
         &self.blocks[id]
     }
 
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
     pub fn get_block_id(&self, name: &str) -> usize {
         self.block_name_to_id[name]
     }
 
+    pub fn find_block_id(&self, name: &str) -> Option<usize> {
+        self.block_name_to_id.get(name).copied()
+    }
+
     pub fn get_model(&self, id: usize) -> &Model {
         &self.models[id]
     }
@@ -93,12 +129,54 @@ impl Registry {
         self.model_name_to_id[name]
     }
 
+    pub fn get_biome(&self, id: usize) -> &Biome {
+        &self.biomes[id]
+    }
+
+    pub fn find_biome_id(&self, name: &str) -> Option<usize> {
+        self.biome_name_to_id.get(name).copied()
+    }
+
+    pub fn get_structure(&self, id: usize) -> &Structure {
+        &self.structures[id]
+    }
+
+    pub fn find_structure_id(&self, name: &str) -> Option<usize> {
+        self.structure_name_to_id.get(name).copied()
+    }
+
+    pub fn get_ore(&self, id: usize) -> &Ore {
+        &self.ores[id]
+    }
+
+    pub fn find_ore_id(&self, name: &str) -> Option<usize> {
+        self.ore_name_to_id.get(name).copied()
+    }
+
+    pub fn find_world_gen(&self, name: &str) -> Option<&WorldGenDef> {
+        self.world_gen_name_to_id
+            .get(name)
+            .map(|&id| &self.world_gens[id])
+    }
+
+    fn resolve_decoration(&self, def: &DecorationDef) -> Decoration {
+        Decoration {
+            block: self.get_block_id(&def.block),
+            on: def.on.iter().map(|b| self.get_block_id(b)).collect(),
+            chance: def.chance,
+        }
+    }
+
     fn post_load(
         &mut self,
         asset_server: Res<AssetServer>,
         images: Res<Assets<Image>>,
         block_defs: Res<Assets<BlockDef>>,
         model_defs: Res<Assets<ModelDef>>,
+        biome_defs: Res<Assets<BiomeDef>>,
+        structure_defs: Res<Assets<StructureDef>>,
+        ore_defs: Res<Assets<OreDef>>,
+        world_gen_defs: Res<Assets<WorldGenDef>>,
     ) {
         // Set up id maps
         self.texture_name_to_id.clear();
@@ -118,6 +196,34 @@ impl Registry {
             self.model_name_to_id.insert(m.identifier.clone(), i);
         }
 
+        self.biome_name_to_id.clear();
+        for i in 0..self.biome_def_handles.len() {
+            let b = biome_defs.get(self.biome_def_handles[i].id()).unwrap();
+            self.biome_name_to_id.insert(b.identifier.clone(), i);
+        }
+
+        self.structure_name_to_id.clear();
+        for i in 0..self.structure_def_handles.len() {
+            let s = structure_defs
+                .get(self.structure_def_handles[i].id())
+                .unwrap();
+            self.structure_name_to_id.insert(s.identifier.clone(), i);
+        }
+
+        self.ore_name_to_id.clear();
+        for i in 0..self.ore_def_handles.len() {
+            let o = ore_defs.get(self.ore_def_handles[i].id()).unwrap();
+            self.ore_name_to_id.insert(o.identifier.clone(), i);
+        }
+
+        self.world_gen_name_to_id.clear();
+        for i in 0..self.world_gen_def_handles.len() {
+            let w = world_gen_defs
+                .get(self.world_gen_def_handles[i].id())
+                .unwrap();
+            self.world_gen_name_to_id.insert(w.identifier.clone(), i);
+        }
+
         // Create block array texture
         const SIZE: u32 = 16;
         let texture_count = self.block_texture_handles.len();
@@ -152,6 +258,7 @@ impl Registry {
             let model = Model {
                 identifier: model_def.identifier.clone(),
                 faces: model_def.faces.clone(),
+                collision: model_def.collision_boxes(),
             };
             self.models.push(model);
         }
@@ -167,9 +274,96 @@ impl Registry {
                 identifier: block_def.identifier.clone(),
                 model,
                 textures,
+                motion_blocking: block_def.motion_blocking,
+                climbable: block_def.climbable,
+                block_entity: block_def.block_entity,
+                hardness: block_def.hardness.max(0.),
+                interaction: block_def.interaction.clone(),
             };
             self.blocks.push(block);
         }
+
+        for i in 0..self.biome_def_handles.len() {
+            let biome_def = biome_defs.get(self.biome_def_handles[i].id()).unwrap();
+            let decorations = biome_def
+                .decorations
+                .iter()
+                .map(|d| self.resolve_decoration(d))
+                .collect();
+            let biome = Biome {
+                climate: Vec2::new(biome_def.temperature, biome_def.humidity),
+                top: self.get_block_id(&biome_def.top),
+                filler: self.get_block_id(&biome_def.filler),
+                depth: biome_def.depth,
+                height: biome_def.height,
+                height_falloff: biome_def.height_falloff,
+                density_offset: biome_def.density_offset,
+                decorations,
+            };
+            self.biomes.push(biome);
+        }
+
+        for i in 0..self.structure_def_handles.len() {
+            let structure_def = structure_defs
+                .get(self.structure_def_handles[i].id())
+                .unwrap();
+            let structure = match &structure_def.shape {
+                ShapeDef::Tree {
+                    trunk,
+                    leaves,
+                    min_height,
+                    max_height,
+                    canopy_radius,
+                } => Structure::Tree {
+                    trunk: self.get_block_id(trunk),
+                    leaves: self.get_block_id(leaves),
+                    min_height: *min_height,
+                    max_height: *max_height,
+                    canopy_radius: *canopy_radius,
+                },
+                ShapeDef::Boulder {
+                    block,
+                    min_radius,
+                    max_radius,
+                } => Structure::Boulder {
+                    block: self.get_block_id(block),
+                    min_radius: *min_radius,
+                    max_radius: *max_radius,
+                },
+                ShapeDef::Template { blocks } => Structure::Template {
+                    blocks: blocks
+                        .iter()
+                        .map(|(offset, block)| (*offset, self.get_block_id(block)))
+                        .collect(),
+                },
+            };
+            self.structures.push(structure);
+        }
+
+        for i in 0..self.ore_def_handles.len() {
+            let ore_def = ore_defs.get(self.ore_def_handles[i].id()).unwrap();
+            let ore = Ore {
+                block: self.get_block_id(&ore_def.block),
+                min_height: ore_def.min_height,
+                max_height: ore_def.max_height,
+                vein_size: ore_def.vein_size,
+                attempts: ore_def.attempts,
+                replaceable: ore_def
+                    .replaceable
+                    .iter()
+                    .map(|b| self.get_block_id(b))
+                    .collect(),
+            };
+            self.ores.push(ore);
+        }
+
+        // World generators are resolved when a world is loaded, once we know which one it uses.
+        for i in 0..self.world_gen_def_handles.len() {
+            let world_gen_def = world_gen_defs
+                .get(self.world_gen_def_handles[i].id())
+                .unwrap();
+            self.world_gens.push(world_gen_def.clone());
+        }
     }
 }
 
@@ -179,6 +373,19 @@ fn construct_asset_registry(
     images: Res<Assets<Image>>,
     blocks: Res<Assets<BlockDef>>,
     models: Res<Assets<ModelDef>>,
+    biomes: Res<Assets<BiomeDef>>,
+    structures: Res<Assets<StructureDef>>,
+    ores: Res<Assets<OreDef>>,
+    world_gens: Res<Assets<WorldGenDef>>,
 ) {
-    registry.post_load(asset_server, images, blocks, models);
+    registry.post_load(
+        asset_server,
+        images,
+        blocks,
+        models,
+        biomes,
+        structures,
+        ores,
+        world_gens,
+    );
 }
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+
+use super::block::BlockType;
+
+#[derive(serde::Deserialize, Asset, TypePath)]
+pub struct StructureDef {
+    pub identifier: String,
+    pub shape: ShapeDef,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub enum ShapeDef {
+    Tree {
+        trunk: String,
+        leaves: String,
+        min_height: u32,
+        max_height: u32,
+        canopy_radius: u32,
+    },
+    Boulder {
+        block: String,
+        min_radius: f32,
+        max_radius: f32,
+    },
+    /// A fixed set of blocks, offset from the air block just above the ground.
+    Template { blocks: Vec<([i32; 3], String)> },
+}
+
+#[derive(Clone)]
+pub enum Structure {
+    Tree {
+        trunk: BlockType,
+        leaves: BlockType,
+        min_height: u32,
+        max_height: u32,
+        canopy_radius: u32,
+    },
+    Boulder {
+        block: BlockType,
+        min_radius: f32,
+        max_radius: f32,
+    },
+    Template {
+        blocks: Vec<([i32; 3], BlockType)>,
+    },
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+#[derive(serde::Deserialize, Asset, TypePath, Clone)]
+pub struct WorldGenDef {
+    pub identifier: String,
+    pub stages: Vec<StageDef>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub enum StageDef {
+    /// Fills every voxel where the node tree's output is above `threshold` with `block`.
+    Density {
+        node_tree: String,
+        frequency: f32,
+        threshold: f32,
+        block: String,
+    },
+    /// Fills every column up to a height from 2D noise, summed over `octaves` and then mapped
+    /// through `curve`. An alternative to [`StageDef::Density`] without overhangs.
+    Heightmap {
+        height: NoiseDef,
+        octaves: u32,
+        /// Frequency multiplier between octaves.
+        lacunarity: f32,
+        /// Amplitude multiplier between octaves.
+        persistence: f32,
+        /// `(noise, height)` points, flat stretches in here make for eroded plateaus and valleys.
+        curve: Vec<(f32, f32)>,
+        block: String,
+    },
+    /// Stacks `(block, thickness)` layers upwards from `bottom` in every column, for superflat
+    /// worlds.
+    Layers {
+        bottom: i32,
+        layers: Vec<(String, u32)>,
+    },
+    /// Lays out one of every registered block except air in a grid at `height`, for checking
+    /// how they all look.
+    BlockGrid { height: i32, spacing: u32 },
+    /// Covers exposed `replaceable` blocks with `top`, and the `depth - 1` blocks below with
+    /// `filler`.
+    Surface {
+        top: String,
+        filler: String,
+        depth: usize,
+        replaceable: Vec<String>,
+    },
+    /// Picks a biome for each column from climate noise. Biomes nudge the density, override the
+    /// surface blocks and bring their own decorations.
+    Biomes {
+        temperature: NoiseDef,
+        humidity: NoiseDef,
+        /// How far apart in climate space biomes still get blended together.
+        blend: f32,
+        biomes: Vec<String>,
+    },
+    /// Hollows out open caves wherever the node tree's output is above `threshold`, below
+    /// `max_height`.
+    Caves {
+        node_tree: String,
+        frequency: f32,
+        threshold: f32,
+        max_height: i32,
+    },
+    /// Digs winding worms through the terrain. A large `vertical_scale` with a small `max_pitch`
+    /// makes ravines rather than tunnels.
+    Worms {
+        /// Seeds this stage's worms, so each `Worms` stage needs its own. Renaming one reshapes
+        /// its caves in chunks that haven't been generated yet.
+        name: String,
+        /// Chance of a chunk starting any worms.
+        chance: f32,
+        max_count: u32,
+        length: (u32, u32),
+        radius: (f32, f32),
+        vertical_scale: f32,
+        /// Steepest slope, in radians.
+        max_pitch: f32,
+    },
+    /// Scatters veins of each of the listed ores through their host blocks.
+    Ores { ores: Vec<String> },
+    /// Grows structures on the surface. They may spill over into neighbouring chunks.
+    Structures { placements: Vec<PlacementDef> },
+    /// Places `block` in the air above any of the `on` blocks.
+    Decorator {
+        block: String,
+        on: Vec<String>,
+        chance: f32,
+    },
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct NoiseDef {
+    pub node_tree: String,
+    pub frequency: f32,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PlacementDef {
+    pub structure: String,
+    pub on: Vec<String>,
+    /// How many columns of each chunk to try.
+    pub attempts: u32,
+    pub chance: f32,
+}
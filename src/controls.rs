@@ -0,0 +1,403 @@
+//! Maps raw input onto [`Action`]s, using bindings from `controls.ron`. Keyboard, mouse and
+//! gamepads all feed the same actions, so systems never need to care which one is in use.
+
+use std::{collections::BTreeMap, fs};
+
+use bevy::{
+    input::{mouse::MouseWheel, InputSystem},
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+const CONTROLS_PATH: &str = "./controls.ron";
+/// How far an analog input has to go before it counts as pressed.
+const ANALOG_PRESS_THRESHOLD: f32 = 0.5;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Controls::load());
+        app.init_resource::<ActionState>();
+        app.init_resource::<KeyboardCapture>();
+        app.add_systems(PreUpdate, update_action_state.after(InputSystem));
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    LookUp,
+    LookDown,
+    LookLeft,
+    LookRight,
+    /// Jumps while walking, rises while flying and climbs up ladders.
+    Jump,
+    /// Sneaks while walking, sinks while flying and climbs down ladders.
+    Crouch,
+    Sprint,
+    ToggleFlying,
+    IncreaseSpeed,
+    DecreaseSpeed,
+    /// Grabs or releases the cursor, and with it control of the player.
+    ToggleControl,
+    ToggleInventory,
+    Break,
+    Place,
+    Pick,
+    HotbarNext,
+    HotbarPrevious,
+    /// Selects a hotbar slot, counting from zero.
+    HotbarSlot(u8),
+    SaveAll,
+    LoadAll,
+    ToggleDebugUi,
+    ToggleVsync,
+    ToggleWireframe,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    WheelUp,
+    WheelDown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum GamepadBinding {
+    Button(GamepadButton),
+    /// One direction of an axis, so a stick can drive a pair of opposing actions.
+    Axis(GamepadAxis, AxisDirection),
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct StickSettings {
+    /// Stick deflection below this is ignored, and the rest is rescaled to start from zero.
+    pub deadzone: f32,
+    /// Exponent applied to the rescaled deflection. Above 1 gives finer control near the centre.
+    pub curve: f32,
+}
+
+impl StickSettings {
+    fn apply(&self, stick: Vec2) -> Vec2 {
+        let length = stick.length().min(1.);
+        if length <= self.deadzone {
+            return Vec2::ZERO;
+        }
+
+        let scaled = ((length - self.deadzone) / (1. - self.deadzone)).powf(self.curve);
+        stick / stick.length() * scaled
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct GamepadControls {
+    pub left_stick: StickSettings,
+    pub right_stick: StickSettings,
+    /// Degrees per second to turn with a look action fully held.
+    pub look_speed: f32,
+}
+
+impl Default for GamepadControls {
+    fn default() -> Self {
+        Self {
+            left_stick: StickSettings {
+                deadzone: 0.15,
+                curve: 1.,
+            },
+            right_stick: StickSettings {
+                deadzone: 0.15,
+                curve: 2.,
+            },
+            look_speed: 180.,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Controls {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+    #[serde(default)]
+    pub gamepad_bindings: BTreeMap<Action, Vec<GamepadBinding>>,
+    pub mouse_sensitivity: f32,
+    #[serde(default)]
+    pub gamepad: GamepadControls,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        use Binding::*;
+
+        let mut bindings = BTreeMap::from([
+            (Action::MoveForward, vec![Key(KeyCode::KeyW)]),
+            (Action::MoveBackward, vec![Key(KeyCode::KeyS)]),
+            (Action::MoveLeft, vec![Key(KeyCode::KeyA)]),
+            (Action::MoveRight, vec![Key(KeyCode::KeyD)]),
+            (Action::Jump, vec![Key(KeyCode::Space)]),
+            (Action::Crouch, vec![Key(KeyCode::ShiftLeft)]),
+            (Action::Sprint, vec![Key(KeyCode::ControlLeft)]),
+            (Action::ToggleFlying, vec![Key(KeyCode::KeyF)]),
+            (Action::IncreaseSpeed, vec![Key(KeyCode::Equal)]),
+            (Action::DecreaseSpeed, vec![Key(KeyCode::Minus)]),
+            (Action::ToggleControl, vec![Key(KeyCode::Escape)]),
+            (Action::ToggleInventory, vec![Key(KeyCode::KeyE)]),
+            (Action::Break, vec![Mouse(MouseButton::Left)]),
+            (Action::Place, vec![Mouse(MouseButton::Right)]),
+            (Action::Pick, vec![Mouse(MouseButton::Middle)]),
+            (Action::HotbarNext, vec![WheelUp]),
+            (Action::HotbarPrevious, vec![WheelDown]),
+            (Action::SaveAll, vec![Key(KeyCode::KeyO)]),
+            (Action::LoadAll, vec![Key(KeyCode::KeyP)]),
+            (Action::ToggleDebugUi, vec![Key(KeyCode::Backquote)]),
+            (Action::ToggleVsync, vec![Key(KeyCode::KeyV)]),
+            (Action::ToggleWireframe, vec![Key(KeyCode::KeyM)]),
+        ]);
+
+        let digits = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+        for (slot, key) in digits.into_iter().enumerate() {
+            bindings.insert(Action::HotbarSlot(slot as u8), vec![Key(key)]);
+        }
+
+        let gamepad_bindings = {
+            use AxisDirection::*;
+            use GamepadBinding::*;
+
+            BTreeMap::from([
+                (
+                    Action::MoveForward,
+                    vec![Axis(GamepadAxis::LeftStickY, Positive)],
+                ),
+                (
+                    Action::MoveBackward,
+                    vec![Axis(GamepadAxis::LeftStickY, Negative)],
+                ),
+                (
+                    Action::MoveLeft,
+                    vec![Axis(GamepadAxis::LeftStickX, Negative)],
+                ),
+                (
+                    Action::MoveRight,
+                    vec![Axis(GamepadAxis::LeftStickX, Positive)],
+                ),
+                (
+                    Action::LookUp,
+                    vec![Axis(GamepadAxis::RightStickY, Positive)],
+                ),
+                (
+                    Action::LookDown,
+                    vec![Axis(GamepadAxis::RightStickY, Negative)],
+                ),
+                (
+                    Action::LookLeft,
+                    vec![Axis(GamepadAxis::RightStickX, Negative)],
+                ),
+                (
+                    Action::LookRight,
+                    vec![Axis(GamepadAxis::RightStickX, Positive)],
+                ),
+                (Action::Jump, vec![Button(GamepadButton::South)]),
+                (Action::Crouch, vec![Button(GamepadButton::RightThumb)]),
+                (Action::Sprint, vec![Button(GamepadButton::LeftThumb)]),
+                (Action::ToggleFlying, vec![Button(GamepadButton::North)]),
+                (Action::IncreaseSpeed, vec![Button(GamepadButton::DPadUp)]),
+                (Action::DecreaseSpeed, vec![Button(GamepadButton::DPadDown)]),
+                (Action::ToggleControl, vec![Button(GamepadButton::Start)]),
+                (Action::ToggleInventory, vec![Button(GamepadButton::Select)]),
+                (Action::Break, vec![Button(GamepadButton::RightTrigger2)]),
+                (Action::Place, vec![Button(GamepadButton::LeftTrigger2)]),
+                (Action::Pick, vec![Button(GamepadButton::West)]),
+                (
+                    Action::HotbarNext,
+                    vec![Button(GamepadButton::RightTrigger)],
+                ),
+                (
+                    Action::HotbarPrevious,
+                    vec![Button(GamepadButton::LeftTrigger)],
+                ),
+            ])
+        };
+
+        Self {
+            bindings,
+            gamepad_bindings,
+            mouse_sensitivity: 0.0001,
+            gamepad: GamepadControls::default(),
+        }
+    }
+}
+
+impl Controls {
+    /// Reads `controls.ron`, filling in defaults for any actions it's missing and writing the
+    /// result back so there's always a complete file to edit.
+    fn load() -> Self {
+        let defaults = Self::default();
+        let mut controls = match fs::read_to_string(CONTROLS_PATH) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(|e| {
+                warn!("failed to parse {CONTROLS_PATH}, using default controls: {e}");
+                defaults.clone()
+            }),
+            Err(_) => defaults.clone(),
+        };
+
+        let mut changed = !fs::exists(CONTROLS_PATH).unwrap_or(false);
+        for (action, bindings) in defaults.bindings {
+            if !controls.bindings.contains_key(&action) {
+                controls.bindings.insert(action, bindings);
+                changed = true;
+            }
+        }
+        for (action, bindings) in defaults.gamepad_bindings {
+            if !controls.gamepad_bindings.contains_key(&action) {
+                controls.gamepad_bindings.insert(action, bindings);
+                changed = true;
+            }
+        }
+
+        if changed {
+            controls.save();
+        }
+        controls
+    }
+
+    pub fn save(&self) {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).unwrap();
+        if let Err(e) = fs::write(CONTROLS_PATH, contents) {
+            warn!("failed to write {CONTROLS_PATH}: {e}");
+        }
+    }
+}
+
+/// Which actions are held and which started this frame, along with how far each is held for
+/// analog inputs.
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    values: HashMap<Action, f32>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// From 0 to 1. Digital inputs are always one or the other.
+    pub fn value(&self, action: Action) -> f32 {
+        self.values.get(&action).copied().unwrap_or(0.)
+    }
+
+    fn set(&mut self, action: Action, value: f32, pressed: bool, just_pressed: bool) {
+        let current = self.values.entry(action).or_default();
+        *current = current.max(value);
+        if pressed {
+            self.pressed.insert(action);
+        }
+        if just_pressed {
+            self.just_pressed.insert(action);
+        }
+    }
+}
+
+/// While set, keys are left for text input and don't trigger any actions.
+#[derive(Resource, Default)]
+pub struct KeyboardCapture(pub bool);
+
+/// Run condition for systems that should only run when `action` starts.
+pub fn action_just_pressed(action: Action) -> impl Fn(Res<ActionState>) -> bool {
+    move |state: Res<ActionState>| state.just_pressed(action)
+}
+
+fn update_action_state(
+    controls: Res<Controls>,
+    keyboard_capture: Res<KeyboardCapture>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut wheel: EventReader<MouseWheel>,
+    gamepads: Query<&Gamepad>,
+    mut state: ResMut<ActionState>,
+) {
+    let wheel_delta: f32 = wheel.read().map(|event| event.y).sum();
+
+    let previously_pressed = std::mem::take(&mut state.pressed);
+    state.just_pressed.clear();
+    state.values.clear();
+
+    for (&action, bindings) in &controls.bindings {
+        for binding in bindings {
+            // A wheel notch is over as soon as it happens, so it's pressed for a single frame.
+            let (pressed, just_pressed) = match *binding {
+                Binding::Key(_) if keyboard_capture.0 => (false, false),
+                Binding::Key(key) => (keys.pressed(key), keys.just_pressed(key)),
+                Binding::Mouse(button) => (
+                    mouse_buttons.pressed(button),
+                    mouse_buttons.just_pressed(button),
+                ),
+                Binding::WheelUp => (wheel_delta > 0., wheel_delta > 0.),
+                Binding::WheelDown => (wheel_delta < 0., wheel_delta < 0.),
+            };
+
+            state.set(action, f32::from(pressed), pressed, just_pressed);
+        }
+    }
+
+    for gamepad in &gamepads {
+        let left_stick = controls.gamepad.left_stick.apply(gamepad.left_stick());
+        let right_stick = controls.gamepad.right_stick.apply(gamepad.right_stick());
+
+        for (&action, bindings) in &controls.gamepad_bindings {
+            for binding in bindings {
+                let value = match *binding {
+                    GamepadBinding::Button(button) => gamepad
+                        .get(button)
+                        .unwrap_or(f32::from(gamepad.pressed(button))),
+                    GamepadBinding::Axis(axis, direction) => {
+                        let value = match axis {
+                            GamepadAxis::LeftStickX => left_stick.x,
+                            GamepadAxis::LeftStickY => left_stick.y,
+                            GamepadAxis::RightStickX => right_stick.x,
+                            GamepadAxis::RightStickY => right_stick.y,
+                            _ => gamepad.get(axis).unwrap_or(0.),
+                        };
+                        match direction {
+                            AxisDirection::Positive => value.max(0.),
+                            AxisDirection::Negative => (-value).max(0.),
+                        }
+                    }
+                };
+
+                // Gamepad state is polled rather than evented, so a press starts when the
+                // action wasn't already held last frame.
+                let pressed = value >= ANALOG_PRESS_THRESHOLD;
+                let just_pressed = pressed && !previously_pressed.contains(&action);
+                state.set(action, value, pressed, just_pressed);
+            }
+        }
+    }
+}
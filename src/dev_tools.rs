@@ -6,13 +6,15 @@ use bevy::{
         ui_debug_overlay::{DebugUiPlugin, UiDebugOptions},
     },
     diagnostic::FrameTimeDiagnosticsPlugin,
-    input::common_conditions::input_just_pressed,
     pbr::wireframe::WireframeConfig,
     prelude::*,
     window::PresentMode,
 };
 
-use crate::screens::Screen;
+use crate::{
+    controls::{action_just_pressed, Action},
+    screens::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((DebugUiPlugin, FrameTimeDiagnosticsPlugin));
@@ -20,15 +22,13 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         Update,
         (
-            toggle_debug_ui.run_if(input_just_pressed(TOGGLE_KEY)),
-            toggle_vsync.run_if(input_just_pressed(KeyCode::KeyV)),
-            toggle_wireframe.run_if(input_just_pressed(KeyCode::KeyM)),
+            toggle_debug_ui.run_if(action_just_pressed(Action::ToggleDebugUi)),
+            toggle_vsync.run_if(action_just_pressed(Action::ToggleVsync)),
+            toggle_wireframe.run_if(action_just_pressed(Action::ToggleWireframe)),
         ),
     );
 }
 
-const TOGGLE_KEY: KeyCode = KeyCode::Backquote;
-
 fn toggle_debug_ui(mut options: ResMut<UiDebugOptions>) {
     options.toggle();
 }
@@ -8,6 +8,26 @@ pub const SAVE_TIME_DIAGNOSTIC: DiagnosticPath = DiagnosticPath::const_new("Chun
 pub const LOAD_TIME_DIAGNOSTIC: DiagnosticPath = DiagnosticPath::const_new("Chunk Loading");
 pub const GEN_TIME_DIAGNOSTIC: DiagnosticPath = DiagnosticPath::const_new("Chunk Generation");
 
+pub const MESHING_FRAME_DIAGNOSTIC: DiagnosticPath =
+    DiagnosticPath::const_new("Chunk Meshing Per Frame");
+pub const SAVE_FRAME_DIAGNOSTIC: DiagnosticPath =
+    DiagnosticPath::const_new("Chunk Saving Per Frame");
+pub const LOAD_FRAME_DIAGNOSTIC: DiagnosticPath =
+    DiagnosticPath::const_new("Chunk Loading Per Frame");
+pub const GEN_FRAME_DIAGNOSTIC: DiagnosticPath =
+    DiagnosticPath::const_new("Chunk Generation Per Frame");
+
+pub const MESHING_COUNT_DIAGNOSTIC: DiagnosticPath =
+    DiagnosticPath::const_new("Chunks Meshed Per Frame");
+pub const SAVE_COUNT_DIAGNOSTIC: DiagnosticPath =
+    DiagnosticPath::const_new("Chunks Saved Per Frame");
+pub const LOAD_COUNT_DIAGNOSTIC: DiagnosticPath =
+    DiagnosticPath::const_new("Chunks Loaded Per Frame");
+pub const GEN_COUNT_DIAGNOSTIC: DiagnosticPath =
+    DiagnosticPath::const_new("Chunks Generated Per Frame");
+
+pub const CHUNKS_PENDING_DIAGNOSTIC: DiagnosticPath = DiagnosticPath::const_new("Chunks Pending");
+
 pub struct DiagnosticsPlugin;
 
 impl Plugin for DiagnosticsPlugin {
@@ -16,5 +36,17 @@ impl Plugin for DiagnosticsPlugin {
         app.register_diagnostic(Diagnostic::new(SAVE_TIME_DIAGNOSTIC).with_suffix("μs"));
         app.register_diagnostic(Diagnostic::new(LOAD_TIME_DIAGNOSTIC).with_suffix("μs"));
         app.register_diagnostic(Diagnostic::new(GEN_TIME_DIAGNOSTIC).with_suffix("μs"));
+
+        app.register_diagnostic(Diagnostic::new(MESHING_FRAME_DIAGNOSTIC).with_suffix("μs"));
+        app.register_diagnostic(Diagnostic::new(SAVE_FRAME_DIAGNOSTIC).with_suffix("μs"));
+        app.register_diagnostic(Diagnostic::new(LOAD_FRAME_DIAGNOSTIC).with_suffix("μs"));
+        app.register_diagnostic(Diagnostic::new(GEN_FRAME_DIAGNOSTIC).with_suffix("μs"));
+
+        app.register_diagnostic(Diagnostic::new(MESHING_COUNT_DIAGNOSTIC));
+        app.register_diagnostic(Diagnostic::new(SAVE_COUNT_DIAGNOSTIC));
+        app.register_diagnostic(Diagnostic::new(LOAD_COUNT_DIAGNOSTIC));
+        app.register_diagnostic(Diagnostic::new(GEN_COUNT_DIAGNOSTIC));
+
+        app.register_diagnostic(Diagnostic::new(CHUNKS_PENDING_DIAGNOSTIC));
     }
 }
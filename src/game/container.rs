@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+
+use crate::{
+    assets::Registry,
+    screens::Screen,
+    world::{BlockEntity, BlockEntityData, BlockEntityRemoved, SpawnBlockEntity},
+};
+
+use super::{
+    interaction::{AppBlockInteractionExt, BlockInteraction, InteractionTarget},
+    items::{fill_slots, insert_stack, ItemStack, SpawnDrop},
+    player::Hotbar,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_block_interaction("container", Container);
+    app.add_systems(
+        Update,
+        drop_removed_contents.run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Slots in a container that doesn't set a `slots` property.
+const DEFAULT_CONTAINER_SLOTS: usize = 27;
+
+/// Stores the player's held stack in the block, or hands back the last stack stored when their
+/// hand is empty. The contents are kept in the block's block entity.
+struct Container;
+
+impl BlockInteraction for Container {
+    fn interact(&self, world: &mut World, target: &InteractionTarget) {
+        let slot_count = target
+            .properties
+            .get("slots")
+            .and_then(|slots| slots.parse().ok())
+            .unwrap_or(DEFAULT_CONTAINER_SLOTS);
+
+        // Generated blocks don't get a block entity until they're first used.
+        let existing = world
+            .query::<(Entity, &BlockEntity)>()
+            .iter(world)
+            .find(|(_, block_entity)| block_entity.position == target.position)
+            .map(|(id, _)| id);
+        let saved = existing
+            .and_then(|id| world.get::<BlockEntityData>(id))
+            .and_then(|data| data.inventory.clone())
+            .unwrap_or_default();
+
+        let registry = world.resource::<Registry>();
+        // Blocks that have since been removed leave an empty slot.
+        let mut slots = fill_slots(
+            slot_count,
+            saved.iter().map(|slot| {
+                let (identifier, count) = slot.as_ref()?;
+                Some(ItemStack::new(registry.find_block_id(identifier)?, *count))
+            }),
+        );
+
+        let Some(mut hotbar) = world.get_mut::<Hotbar>(target.player) else {
+            return;
+        };
+        let active_slot = hotbar.active_slot;
+        match hotbar.slots[active_slot] {
+            Some(held) => {
+                let remaining = insert_stack(&mut slots, held);
+                if remaining == held.count {
+                    return;
+                }
+                hotbar.slots[active_slot] =
+                    (remaining > 0).then(|| ItemStack::new(held.block, remaining));
+            }
+            None => {
+                let Some(last) = slots.iter_mut().rev().find(|slot| slot.is_some()) else {
+                    return;
+                };
+                hotbar.slots[active_slot] = last.take();
+            }
+        }
+
+        let registry = world.resource::<Registry>();
+        let inventory = slots
+            .iter()
+            .map(|slot| {
+                slot.map(|stack| {
+                    let identifier = registry.get_block(stack.block).identifier.clone();
+                    (identifier, stack.count)
+                })
+            })
+            .collect();
+        match existing {
+            Some(id) => {
+                if let Some(mut data) = world.get_mut::<BlockEntityData>(id) {
+                    data.inventory = Some(inventory);
+                }
+            }
+            None => {
+                world.send_event(SpawnBlockEntity {
+                    position: target.position,
+                    data: BlockEntityData {
+                        inventory: Some(inventory),
+                        ..default()
+                    },
+                });
+            }
+        }
+    }
+}
+
+/// Spills out whatever a container held when it's broken, in either game mode, since the stacks
+/// were put there by the player.
+fn drop_removed_contents(
+    registry: Res<Registry>,
+    mut removed_events: EventReader<BlockEntityRemoved>,
+    mut drop_events: EventWriter<SpawnDrop>,
+) {
+    for event in removed_events.read() {
+        let Some(inventory) = &event.data.inventory else {
+            continue;
+        };
+
+        for (identifier, count) in inventory.iter().flatten() {
+            let Some(block) = registry.find_block_id(identifier) else {
+                continue;
+            };
+            drop_events.send(SpawnDrop {
+                stack: ItemStack::new(block, *count),
+                position: event.position.as_vec3() + Vec3::splat(0.5),
+            });
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    assets::{BlockType, Registry},
+    render::ChunkNeedsMeshing,
+    screens::Screen,
+    world::{Chunk, VoxelCollider, VoxelWorld},
+};
+
+use super::player::player_place_block;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<InteractWithBlock>();
+    app.register_block_interaction("swap", SwapBlock);
+    app.add_systems(
+        Update,
+        run_block_interactions
+            .after(player_place_block)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Something a block does when it's used, looked up by the `behaviour` its definition names.
+/// Gets the whole world, so it can change blocks, open UI or run anything else it likes.
+pub trait BlockInteraction: Send + Sync + 'static {
+    fn interact(&self, world: &mut World, target: &InteractionTarget);
+}
+
+impl<F> BlockInteraction for F
+where
+    F: Fn(&mut World, &InteractionTarget) + Send + Sync + 'static,
+{
+    fn interact(&self, world: &mut World, target: &InteractionTarget) {
+        self(world, target);
+    }
+}
+
+pub struct InteractionTarget {
+    pub position: IVec3,
+    pub player: Entity,
+    /// The `properties` from the block's definition.
+    pub properties: HashMap<String, String>,
+}
+
+#[derive(Resource, Default)]
+pub struct BlockInteractions(HashMap<String, Arc<dyn BlockInteraction>>);
+
+pub trait AppBlockInteractionExt {
+    /// Makes `behaviour` available to blocks under `identifier`, replacing any already there.
+    fn register_block_interaction(
+        &mut self,
+        identifier: impl Into<String>,
+        behaviour: impl BlockInteraction,
+    ) -> &mut Self;
+}
+
+impl AppBlockInteractionExt for App {
+    fn register_block_interaction(
+        &mut self,
+        identifier: impl Into<String>,
+        behaviour: impl BlockInteraction,
+    ) -> &mut Self {
+        self.init_resource::<BlockInteractions>();
+        self.world_mut()
+            .resource_mut::<BlockInteractions>()
+            .0
+            .insert(identifier.into(), Arc::new(behaviour));
+        self
+    }
+}
+
+/// Sent when a player uses a block that has an interaction.
+#[derive(Event)]
+pub struct InteractWithBlock {
+    pub position: IVec3,
+    pub player: Entity,
+}
+
+fn run_block_interactions(world: &mut World) {
+    let events: Vec<_> = world
+        .resource_mut::<Events<InteractWithBlock>>()
+        .drain()
+        .collect();
+
+    for event in events {
+        // The block may have changed since the event was sent.
+        let Some(block) = world.resource::<VoxelWorld>().get_block_at(event.position) else {
+            continue;
+        };
+        let Some(interaction) = world
+            .resource::<Registry>()
+            .get_block(block)
+            .interaction
+            .clone()
+        else {
+            continue;
+        };
+        let Some(behaviour) = world
+            .resource::<BlockInteractions>()
+            .0
+            .get(&interaction.behaviour)
+            .cloned()
+        else {
+            warn!("no block interaction named `{}`", interaction.behaviour);
+            continue;
+        };
+
+        let target = InteractionTarget {
+            position: event.position,
+            player: event.player,
+            properties: interaction.properties,
+        };
+        behaviour.interact(world, &target);
+    }
+}
+
+/// Sets the block at a world position, then flags its chunk for saving and remeshes it along with
+/// any neighbours it touches.
+pub fn set_block(world: &mut World, pos: IVec3, block: BlockType) {
+    let mut storage = world.resource_mut::<VoxelWorld>();
+    let (chunk_pos, local_pos) = storage.split_pos(pos);
+    storage.set_block_at(pos, block);
+    let needs_meshing = storage.touched_chunks(chunk_pos, local_pos);
+
+    let mut query_chunk = world.query::<(Entity, &mut Chunk)>();
+    let mut remesh = Vec::new();
+    for (id, mut chunk) in query_chunk.iter_mut(world) {
+        if chunk.world_pos == chunk_pos {
+            chunk.dirty = true;
+        }
+
+        if needs_meshing.contains(&chunk.world_pos) {
+            remesh.push(id);
+        }
+    }
+
+    for id in remesh {
+        world.entity_mut(id).insert(ChunkNeedsMeshing);
+    }
+}
+
+/// Replaces the block with the one named by its `into` property, like opening and closing doors.
+struct SwapBlock;
+
+impl BlockInteraction for SwapBlock {
+    fn interact(&self, world: &mut World, target: &InteractionTarget) {
+        let Some(into) = target.properties.get("into") else {
+            warn!("the swap interaction needs an `into` property");
+            return;
+        };
+        let Some(block) = world.resource::<Registry>().find_block_id(into) else {
+            warn!("can't swap into unknown block `{into}`");
+            return;
+        };
+
+        // Like placing, this mustn't shut the player inside the block. Anything they were already
+        // overlapping beforehand isn't this block's fault.
+        let player = world
+            .get::<Transform>(target.player)
+            .zip(world.get::<VoxelCollider>(target.player))
+            .map(|(transform, collider)| collider.aabb(transform.translation));
+        let overlaps = |world: &World| {
+            player.is_some_and(|aabb| world.resource::<VoxelWorld>().overlaps(aabb))
+        };
+        let was_overlapping = overlaps(world);
+        let previous = world.resource::<VoxelWorld>().get_block_at(target.position);
+        world
+            .resource_mut::<VoxelWorld>()
+            .set_block_at(target.position, block);
+        let blocked = !was_overlapping && overlaps(world);
+        if let Some(previous) = previous.filter(|_| blocked) {
+            world
+                .resource_mut::<VoxelWorld>()
+                .set_block_at(target.position, previous);
+            return;
+        }
+
+        set_block(world, target.position, block);
+    }
+}
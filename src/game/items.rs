@@ -0,0 +1,224 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    assets::{BlockType, Registry},
+    render::{quad_mesh, ArrayTextureMaterial},
+    screens::Screen,
+    world::{VoxelBody, VoxelCollider},
+};
+
+use super::player::{Hotbar, Player};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<SpawnDrop>();
+    app.add_systems(
+        Update,
+        (spawn_drops, pick_up_drops, update_drops).run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+pub const MAX_STACK_SIZE: u32 = 64;
+pub const INVENTORY_SLOTS: usize = 27;
+
+/// How close a drop has to be to the player's eyes to be picked up.
+const PICKUP_RADIUS: f32 = 2.0;
+/// Keeps drops from being picked up the moment they're spawned.
+const PICKUP_DELAY: Duration = Duration::from_millis(500);
+const DROP_LIFETIME: Duration = Duration::from_secs(300);
+const DROP_SIZE: f32 = 0.25;
+/// Radians per second.
+const DROP_SPIN_SPEED: f32 = 1.0;
+const DROP_POP_SPEED: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemStack {
+    pub block: BlockType,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(block: BlockType, count: u32) -> Self {
+        Self { block, count }
+    }
+}
+
+/// `slot_count` slots starting with `stacks`, dropping any that don't fit.
+pub fn fill_slots(
+    slot_count: usize,
+    stacks: impl IntoIterator<Item = Option<ItemStack>>,
+) -> Vec<Option<ItemStack>> {
+    let mut slots: Vec<_> = stacks.into_iter().take(slot_count).collect();
+    slots.resize(slot_count, None);
+    slots
+}
+
+/// Adds as much of `stack` to `slots` as fits, topping up matching stacks before filling empty
+/// slots. Returns how many didn't fit.
+pub fn insert_stack(slots: &mut [Option<ItemStack>], stack: ItemStack) -> u32 {
+    let mut remaining = stack.count;
+    for slot in slots.iter_mut().flatten() {
+        if slot.block == stack.block {
+            let moved = (MAX_STACK_SIZE - slot.count.min(MAX_STACK_SIZE)).min(remaining);
+            slot.count += moved;
+            remaining -= moved;
+        }
+    }
+
+    for slot in slots.iter_mut() {
+        if remaining == 0 {
+            break;
+        }
+
+        if slot.is_none() {
+            let moved = remaining.min(MAX_STACK_SIZE);
+            *slot = Some(ItemStack::new(stack.block, moved));
+            remaining -= moved;
+        }
+    }
+
+    remaining
+}
+
+/// Everything the player carries beyond the hotbar. Only used in survival.
+#[derive(Component)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+}
+
+/// Drops `stack` into the world as an item entity at `position`.
+#[derive(Event)]
+pub struct SpawnDrop {
+    pub stack: ItemStack,
+    pub position: Vec3,
+}
+
+#[derive(Component)]
+pub struct DroppedItem {
+    pub stack: ItemStack,
+    pickup_delay: Timer,
+    lifetime: Timer,
+}
+
+/// Drops of the same block share a mesh.
+#[derive(Default)]
+struct DropAssets {
+    material: Option<Handle<ArrayTextureMaterial>>,
+    meshes: HashMap<BlockType, Handle<Mesh>>,
+}
+
+fn spawn_drops(
+    mut commands: Commands,
+    registry: Res<Registry>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ArrayTextureMaterial>>,
+    mut spawn_events: EventReader<SpawnDrop>,
+    mut drop_assets: Local<DropAssets>,
+) {
+    for event in spawn_events.read() {
+        let block = registry.get_block(event.stack.block);
+        let Some(model_id) = block.model else {
+            continue;
+        };
+
+        let material = drop_assets
+            .material
+            .get_or_insert_with(|| {
+                materials.add(ArrayTextureMaterial {
+                    array_texture: registry.block_array_texture.clone(),
+                })
+            })
+            .clone();
+        let mesh = drop_assets
+            .meshes
+            .entry(event.stack.block)
+            .or_insert_with(|| {
+                let (mut vs, mut ns, mut uvs, mut ts) = (vec![], vec![], vec![], vec![]);
+                registry.get_model(model_id).mesh(
+                    &[false; 6],
+                    &[-0.5, -0.5, -0.5],
+                    &mut vs,
+                    &mut ns,
+                    &mut uvs,
+                    &mut ts,
+                    block,
+                );
+                meshes.add(quad_mesh(vs, ns, uvs, ts))
+            })
+            .clone();
+
+        commands.spawn((
+            StateScoped(Screen::Gameplay),
+            DroppedItem {
+                stack: event.stack,
+                pickup_delay: Timer::new(PICKUP_DELAY, TimerMode::Once),
+                lifetime: Timer::new(DROP_LIFETIME, TimerMode::Once),
+            },
+            Mesh3d(mesh),
+            MeshMaterial3d(material),
+            Transform::from_translation(event.position).with_scale(Vec3::splat(DROP_SIZE)),
+            VoxelBody {
+                velocity: Vec3::Y * DROP_POP_SPEED,
+                ..default()
+            },
+            VoxelCollider {
+                half_extents: Vec3::splat(DROP_SIZE / 2.),
+                offset: Vec3::ZERO,
+            },
+        ));
+    }
+}
+
+/// Fills the hotbar before the inventory. Whatever doesn't fit is left on the ground.
+fn pick_up_drops(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query_player: Query<(&Transform, &mut Hotbar, &mut Inventory), With<Player>>,
+    mut query_drops: Query<(Entity, &Transform, &mut DroppedItem), Without<Player>>,
+) {
+    let Ok((player_transform, mut hotbar, mut inventory)) = query_player.get_single_mut() else {
+        return;
+    };
+
+    for (id, transform, mut drop) in &mut query_drops {
+        if !drop.pickup_delay.tick(time.delta()).finished()
+            || transform.translation.distance(player_transform.translation) > PICKUP_RADIUS
+        {
+            continue;
+        }
+
+        // Only flag the hotbar and inventory as changed if something actually went in.
+        let count = drop.stack.count;
+        let remaining = insert_stack(&mut hotbar.bypass_change_detection().slots, drop.stack);
+        if remaining != count {
+            hotbar.set_changed();
+        }
+
+        let stack = ItemStack::new(drop.stack.block, remaining);
+        let left_over = insert_stack(&mut inventory.bypass_change_detection().slots, stack);
+        if left_over != remaining {
+            inventory.set_changed();
+        }
+
+        match left_over {
+            0 => commands.entity(id).despawn_recursive(),
+            _ => drop.stack.count = left_over,
+        }
+    }
+}
+
+fn update_drops(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query_drops: Query<(Entity, &mut Transform, &mut DroppedItem)>,
+) {
+    for (id, mut transform, mut drop) in &mut query_drops {
+        if drop.lifetime.tick(time.delta()).finished() {
+            commands.entity(id).despawn_recursive();
+            continue;
+        }
+
+        transform.rotate_y(DROP_SPIN_SPEED * time.delta_secs());
+    }
+}
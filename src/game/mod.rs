@@ -1,7 +1,16 @@
 use bevy::prelude::*;
 
+mod container;
+pub mod interaction;
+pub mod items;
 pub mod player;
+mod player_data;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((player::plugin));
+    app.add_plugins((
+        container::plugin,
+        interaction::plugin,
+        items::plugin,
+        player::plugin,
+    ));
 }
@@ -1,5 +1,5 @@
 use bevy::{
-    input::mouse::{MouseMotion, MouseWheel},
+    input::mouse::MouseMotion,
     pbr::wireframe::Wireframe,
     prelude::*,
     window::{CursorGrabMode, PrimaryWindow},
@@ -7,52 +7,53 @@ use bevy::{
 
 use crate::{
     assets::{BlockType, Registry},
+    controls::{Action, ActionState, Controls},
     render::ChunkNeedsMeshing,
     screens::Screen,
-    world::{Chunk, VoxelWorld},
+    ui::InventoryOpen,
+    world::{
+        set_aside, BlockEntityData, Chunk, ChunkLoader, GameMode, SpawnBlockEntity, SpawnMoved,
+        VoxelBody, VoxelCollider, VoxelPhysicsSet, VoxelWorld, WorldMetadata, WorldSetup,
+    },
+};
+
+use super::{
+    interaction::InteractWithBlock,
+    items::{fill_slots, Inventory, ItemStack, SpawnDrop, INVENTORY_SLOTS},
+    player_data::{PlayerData, SavedStack, LOCAL_PLAYER_ID},
 };
 
 pub(super) fn plugin(app: &mut App) {
-    app.insert_resource(PlayerMovementControls {
-        toggle_control: KeyCode::Escape,
-        left: KeyCode::KeyA,
-        right: KeyCode::KeyD,
-        up: KeyCode::Space,
-        down: KeyCode::ShiftLeft,
-        forward: KeyCode::KeyW,
-        backward: KeyCode::KeyS,
-        mouse_sensitivity: 0.0001,
-        decrease_speed: KeyCode::Minus,
-        increase_speed: KeyCode::Equal,
-    });
-    app.add_systems(OnEnter(Screen::Gameplay), setup_player);
+    app.init_resource::<HotbarSettings>();
+    app.add_systems(OnEnter(Screen::Gameplay), setup_player.after(WorldSetup));
     app.add_systems(
         Update,
         (
-            player_move,
+            player_toggle_flying,
             player_look,
-            player_toggle_active,
+            player_toggle_active.run_if(not(resource_exists::<InventoryOpen>)),
             player_show_block_highlight,
-            player_break_place_block,
+            player_break_block.after(player_show_block_highlight),
+            player_place_block,
+            player_pick_block,
             player_modify_speed,
             player_scroll_inventory,
+            player_respawn,
+            player_follow_spawn,
+            save_player.run_if(|world: Res<VoxelWorld>| world.save_timer.just_finished()),
         )
             .run_if(in_state(Screen::Gameplay)),
     );
-}
-
-#[derive(Resource)]
-pub struct PlayerMovementControls {
-    pub toggle_control: KeyCode,
-    pub left: KeyCode,
-    pub right: KeyCode,
-    pub up: KeyCode,
-    pub down: KeyCode,
-    pub forward: KeyCode,
-    pub backward: KeyCode,
-    pub mouse_sensitivity: f32,
-    pub increase_speed: KeyCode,
-    pub decrease_speed: KeyCode,
+    app.add_systems(
+        FixedUpdate,
+        player_move
+            .before(VoxelPhysicsSet)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Last,
+        save_player.run_if(in_state(Screen::Gameplay).and(on_event::<AppExit>)),
+    );
 }
 
 #[derive(Component)]
@@ -60,6 +61,8 @@ pub struct MovementSettings {
     base_speed: f32,
     speed: f32,
     active: bool,
+    /// Flying ignores gravity and collision, and uses `speed`. Walking uses the constants below.
+    flying: bool,
 }
 
 impl Default for MovementSettings {
@@ -68,16 +71,38 @@ impl Default for MovementSettings {
             base_speed: 10.,
             speed: 10.,
             active: Default::default(),
+            flying: false,
         }
     }
 }
 
+/// How far above their feet the player's camera sits.
+const EYE_HEIGHT: f32 = 1.6;
+const PLAYER_WIDTH: f32 = 0.6;
+const PLAYER_HEIGHT: f32 = 1.8;
+
+const WALK_SPEED: f32 = 4.3;
+const SPRINT_MULTIPLIER: f32 = 1.3;
+const CROUCH_MULTIPLIER: f32 = 0.3;
+const JUMP_SPEED: f32 = 8.5;
+/// Tallest ledge the player walks up without jumping.
+const STEP_HEIGHT: f32 = 0.6;
+/// How quickly horizontal velocity approaches the target, on the ground and in the air.
+const GROUND_FRICTION: f32 = 20.;
+const AIR_CONTROL: f32 = 2.;
+/// Players falling below this are sent back to spawn.
+const RESPAWN_DEPTH: f32 = -256.;
+
 #[derive(Component)]
 pub struct Player;
 
 #[derive(Component)]
 pub struct HoverHighlight;
 
+/// How far through breaking the [`TargetBlock`] the player is, from 0 to 1.
+#[derive(Component, Default)]
+pub struct BreakProgress(pub f32);
+
 #[derive(Component, Default)]
 pub struct TargetBlock {
     pub chunk_pos: [i32; 3],
@@ -86,34 +111,131 @@ pub struct TargetBlock {
     pub model_id: Option<usize>,
 }
 
+impl TargetBlock {
+    pub fn world_pos(&self, chunk_len: usize) -> IVec3 {
+        let [x, y, z] = self.local_pos;
+        IVec3::from_array(self.chunk_pos) * chunk_len as i32
+            + IVec3::new(x as i32, y as i32, z as i32)
+    }
+}
+
+/// Creative only ever holds single blocks, and never runs out of them.
 #[derive(Component)]
 pub struct Hotbar {
-    pub slots: Vec<Option<BlockType>>,
+    pub slots: Vec<Option<ItemStack>>,
     pub active_slot: usize,
 }
 
-fn setup_player(mut commands: Commands, registry: Res<Registry>, mut meshes: ResMut<Assets<Mesh>>) {
+impl Hotbar {
+    pub fn new(slot_count: usize, stacks: impl IntoIterator<Item = Option<ItemStack>>) -> Self {
+        Self {
+            slots: fill_slots(slot_count, stacks),
+            active_slot: 0,
+        }
+    }
+
+    pub fn active_block(&self) -> Option<BlockType> {
+        self.slots[self.active_slot].map(|stack| stack.block)
+    }
+}
+
+#[derive(Resource)]
+pub struct HotbarSettings {
+    pub slot_count: usize,
+}
+
+impl Default for HotbarSettings {
+    fn default() -> Self {
+        Self { slot_count: 9 }
+    }
+}
+
+fn setup_player(
+    mut commands: Commands,
+    registry: Res<Registry>,
+    metadata: Res<WorldMetadata>,
+    voxel_world: Res<VoxelWorld>,
+    hotbar_settings: Res<HotbarSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let mut transform = Transform::from_translation(metadata.spawn_point() + Vec3::Y * EYE_HEIGHT);
+    let mut movement_settings = MovementSettings::default();
+    let slot_count = hotbar_settings.slot_count.max(1);
+    // Survival players start out with nothing.
+    let starting_blocks = match metadata.game_mode {
+        GameMode::Creative => vec![
+            "grass",
+            "dirt",
+            "stone",
+            "stone_fence",
+            "ladder",
+            "door",
+            "chest",
+        ],
+        GameMode::Survival => vec![],
+    };
+    let mut hotbar = Hotbar::new(
+        slot_count,
+        starting_blocks.into_iter().map(|name| {
+            registry
+                .find_block_id(name)
+                .map(|block| ItemStack::new(block, 1))
+        }),
+    );
+    let mut inventory = Inventory {
+        slots: fill_slots(INVENTORY_SLOTS, []),
+    };
+
+    let saved = match PlayerData::load(&voxel_world.save_dir(), LOCAL_PLAYER_ID) {
+        Ok(saved) => saved,
+        // Starting over would otherwise save over it.
+        Err(e) => {
+            warn!("{e}, starting the player over");
+            set_aside(e.path());
+            None
+        }
+    };
+    if let Some(data) = saved {
+        transform.translation = Vec3::from_array(data.translation);
+        // A zeroed or otherwise broken rotation would normalize to NaN.
+        transform.rotation = Vec4::from_array(data.rotation)
+            .try_normalize()
+            .map_or(Quat::IDENTITY, Quat::from_vec4);
+        movement_settings.speed = data.speed;
+        // Blocks that have since been removed leave an empty slot.
+        let resolve = |slot: &Option<SavedStack>| slot.as_ref().and_then(|s| s.resolve(&registry));
+        if !data.hotbar.is_empty() {
+            hotbar = Hotbar::new(slot_count, data.hotbar.iter().map(resolve));
+            hotbar.active_slot = data.active_slot.min(slot_count - 1);
+        }
+        inventory.slots = fill_slots(INVENTORY_SLOTS, data.inventory.iter().map(resolve));
+    }
+
     commands.spawn((
         StateScoped(Screen::Gameplay),
         Camera3d::default(),
-        // Transform::default(),
-        Transform::from_xyz(-2.0, 5.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        transform,
         Player,
-        MovementSettings::default(),
-        Hotbar {
-            slots: vec![
-                Some(registry.get_block_id("grass")),
-                Some(registry.get_block_id("dirt")),
-                Some(registry.get_block_id("stone")),
-                Some(registry.get_block_id("stone_fence")),
-            ],
-            active_slot: 0,
+        VoxelBody {
+            step_height: STEP_HEIGHT,
+            // Walking steers the velocity directly.
+            friction: 0.,
+            ..default()
         },
+        VoxelCollider {
+            half_extents: Vec3::new(PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH) / 2.,
+            offset: Vec3::Y * (PLAYER_HEIGHT / 2. - EYE_HEIGHT),
+        },
+        ChunkLoader { radius: 4 },
+        movement_settings,
+        hotbar,
+        inventory,
     ));
 
     commands.spawn((
         HoverHighlight,
         TargetBlock::default(),
+        BreakProgress::default(),
         Mesh3d(meshes.add(Cuboid::default())),
         Transform::default(),
         Visibility::Hidden,
@@ -123,9 +245,75 @@ fn setup_player(mut commands: Commands, registry: Res<Registry>, mut meshes: Res
     ));
 }
 
+fn save_player(
+    registry: Res<Registry>,
+    voxel_world: Res<VoxelWorld>,
+    query_player: Query<(&Transform, &MovementSettings, &Hotbar, &Inventory), With<Player>>,
+) {
+    let Ok((transform, movement_settings, hotbar, inventory)) = query_player.get_single() else {
+        return;
+    };
+
+    let save_slots = |slots: &[Option<ItemStack>]| -> Vec<Option<SavedStack>> {
+        slots
+            .iter()
+            .map(|slot| slot.map(|stack| SavedStack::from_stack(&stack, &registry)))
+            .collect()
+    };
+
+    let data = PlayerData {
+        translation: transform.translation.to_array(),
+        rotation: transform.rotation.to_array(),
+        speed: movement_settings.speed,
+        hotbar: save_slots(&hotbar.slots),
+        active_slot: hotbar.active_slot,
+        inventory: save_slots(&inventory.slots),
+    };
+    data.save(&voxel_world.save_dir(), LOCAL_PLAYER_ID);
+}
+
+fn player_respawn(
+    metadata: Res<WorldMetadata>,
+    storage: Res<VoxelWorld>,
+    mut query_player: Query<(&mut Transform, &mut VoxelBody), With<Player>>,
+) {
+    let (mut transform, mut body) = query_player.single_mut();
+    if transform.translation.y >= RESPAWN_DEPTH {
+        return;
+    }
+
+    // Something may have been built over the spawn point since it was chosen.
+    let mut spawn = metadata.spawn_point();
+    if let Some(surface) = storage.surface_height(spawn.x.floor() as i32, spawn.z.floor() as i32) {
+        spawn.y = spawn.y.max(surface as f32 + 1.);
+    }
+    transform.translation = spawn + Vec3::Y * EYE_HEIGHT;
+    body.velocity = Vec3::ZERO;
+}
+
+/// A new world's spawn point can turn out to be blocked once the chunks around it are loaded.
+/// Anyone left stuck inside whatever blocked it goes along with it.
+fn player_follow_spawn(
+    mut moved_events: EventReader<SpawnMoved>,
+    storage: Res<VoxelWorld>,
+    mut query_player: Query<(&mut Transform, &mut VoxelBody, &VoxelCollider), With<Player>>,
+) {
+    let Some(moved) = moved_events.read().last() else {
+        return;
+    };
+    let Ok((mut transform, mut body, collider)) = query_player.get_single_mut() else {
+        return;
+    };
+    if !storage.overlaps(collider.aabb(transform.translation)) {
+        return;
+    }
+
+    transform.translation = moved.to + Vec3::Y * EYE_HEIGHT;
+    body.velocity = Vec3::ZERO;
+}
+
 fn player_modify_speed(
-    keys: Res<ButtonInput<KeyCode>>,
-    controls: Res<PlayerMovementControls>,
+    actions: Res<ActionState>,
     mut query_player: Query<&mut MovementSettings, With<Player>>,
 ) {
     let mut movement_settings = query_player.single_mut();
@@ -136,57 +324,97 @@ fn player_modify_speed(
     let base = movement_settings.base_speed;
     let mut target_speed = movement_settings.speed;
 
-    if keys.just_pressed(controls.increase_speed) {
+    if actions.just_pressed(Action::IncreaseSpeed) {
         target_speed += base;
     }
-    if keys.just_pressed(controls.decrease_speed) {
+    if actions.just_pressed(Action::DecreaseSpeed) {
         target_speed -= base;
     }
 
     movement_settings.speed = f32::clamp(target_speed, base, base * 10.);
 }
 
+fn player_toggle_flying(
+    actions: Res<ActionState>,
+    mut query_player: Query<(&mut MovementSettings, &mut VoxelBody), With<Player>>,
+) {
+    let (mut movement_settings, mut body) = query_player.single_mut();
+    if !movement_settings.active || !actions.just_pressed(Action::ToggleFlying) {
+        return;
+    }
+
+    movement_settings.flying = !movement_settings.flying;
+    body.kinematic = movement_settings.flying;
+    body.sneaking = false;
+    body.velocity = Vec3::ZERO;
+    body.on_ground = false;
+}
+
 fn player_move(
-    keys: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     time: Res<Time>,
-    controls: Res<PlayerMovementControls>,
-    mut query_player: Query<(&mut Transform, &MovementSettings), With<Player>>,
+    mut query_player: Query<(&mut Transform, &MovementSettings, &mut VoxelBody), With<Player>>,
 ) {
-    let (mut transform, movement_settings) = query_player.single_mut();
-    if !movement_settings.active {
+    let (mut transform, movement_settings, mut body) = query_player.single_mut();
+
+    let local_z = transform.local_z();
+    let forward = -Vec3::new(local_z.x, 0., local_z.z).normalize_or_zero();
+    let right = Vec3::new(local_z.z, 0., -local_z.x).normalize_or_zero();
+
+    let held = |action| movement_settings.active && actions.pressed(action);
+    let amount = |action| match movement_settings.active {
+        true => actions.value(action),
+        false => 0.,
+    };
+    let mut target_direction = forward
+        * (amount(Action::MoveForward) - amount(Action::MoveBackward))
+        + right * (amount(Action::MoveRight) - amount(Action::MoveLeft));
+    if movement_settings.flying {
+        target_direction.y = amount(Action::Jump) - amount(Action::Crouch);
+    }
+    // Clamped rather than normalized so a half-tilted stick moves at half speed.
+    target_direction = target_direction.clamp_length_max(1.);
+
+    let dt = time.delta_secs();
+    if movement_settings.flying {
+        transform.translation += target_direction * dt * movement_settings.speed;
         return;
     }
 
-    let local_z = transform.local_z();
-    let forward = -Vec3::new(local_z.x, 0., local_z.z);
-    let right = Vec3::new(local_z.z, 0., -local_z.x);
-
-    let mut target_velocity = Vec3::ZERO;
-    for key in keys.get_pressed() {
-        let k = *key;
-        target_velocity += if k == controls.forward {
-            forward
-        } else if k == controls.backward {
-            -forward
-        } else if k == controls.left {
-            -right
-        } else if k == controls.right {
-            right
-        } else if k == controls.up {
-            Vec3::Y
-        } else if k == controls.down {
-            -Vec3::Y
+    let crouching = held(Action::Crouch);
+    let speed = WALK_SPEED
+        * if crouching {
+            CROUCH_MULTIPLIER
+        } else if held(Action::Sprint) {
+            SPRINT_MULTIPLIER
         } else {
-            Vec3::ZERO
+            1.
         };
-    }
 
-    target_velocity = target_velocity.normalize_or_zero();
-    transform.translation += target_velocity * time.delta_secs() * movement_settings.speed;
+    let friction = if body.on_ground {
+        GROUND_FRICTION
+    } else {
+        AIR_CONTROL
+    };
+    let target_velocity = target_direction * speed;
+    let t = 1. - (-friction * dt).exp();
+    body.velocity.x += (target_velocity.x - body.velocity.x) * t;
+    body.velocity.z += (target_velocity.z - body.velocity.z) * t;
+
+    body.sneaking = crouching;
+
+    if body.climbing {
+        let climb = held(Action::Jump) as i32 - held(Action::Crouch) as i32;
+        body.velocity.y = climb as f32 * body.climb_speed;
+    } else if body.on_ground && held(Action::Jump) {
+        body.velocity.y = JUMP_SPEED;
+    }
 }
 
 fn player_look(
-    controls: Res<PlayerMovementControls>,
+    controls: Res<Controls>,
+    actions: Res<ActionState>,
+    time: Res<Time>,
     mut mouse_motions: EventReader<MouseMotion>,
     query_window: Query<&Window, With<PrimaryWindow>>,
     mut query_player: Query<(&mut Transform, &MovementSettings), With<Player>>,
@@ -206,26 +434,44 @@ fn player_look(
         target_yaw -= (sensitivity * event.delta.x * window_scale).to_radians();
     }
 
+    let look_step = controls.gamepad.look_speed * time.delta_secs();
+    target_pitch += ((actions.value(Action::LookUp) - actions.value(Action::LookDown)) * look_step)
+        .to_radians();
+    target_yaw -= ((actions.value(Action::LookRight) - actions.value(Action::LookLeft))
+        * look_step)
+        .to_radians();
+
     let yaw_rot = Quat::from_axis_angle(Vec3::Y, target_yaw);
     let pitch_rot = Quat::from_axis_angle(Vec3::X, target_pitch.clamp(-1.54, 1.54));
     transform.rotation = yaw_rot * pitch_rot;
 }
 
 fn player_toggle_active(
-    controls: Res<PlayerMovementControls>,
-    keys: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut query_window: Query<&mut Window, With<PrimaryWindow>>,
     mut query_player: Query<&mut MovementSettings, With<Player>>,
 ) {
-    if !keys.just_pressed(controls.toggle_control) {
+    if !actions.just_pressed(Action::ToggleControl) {
         return;
     }
 
     let mut movement_settings = query_player.single_mut();
-    movement_settings.active = !movement_settings.active;
+    let active = !movement_settings.active;
+    set_player_control(
+        &mut movement_settings,
+        &mut query_window.single_mut(),
+        active,
+    );
+}
 
-    let mut window = query_window.single_mut();
-    match movement_settings.active {
+/// Gives or takes control of the player, grabbing or releasing the cursor to match.
+pub fn set_player_control(
+    movement_settings: &mut MovementSettings,
+    window: &mut Window,
+    active: bool,
+) {
+    movement_settings.active = active;
+    match active {
         true => {
             window.cursor_options.grab_mode = CursorGrabMode::Locked;
             window.cursor_options.visible = false;
@@ -238,7 +484,7 @@ fn player_toggle_active(
 }
 
 fn player_scroll_inventory(
-    mut scrolls: EventReader<MouseWheel>,
+    actions: Res<ActionState>,
     mut query_player: Query<(&MovementSettings, &mut Hotbar), With<Player>>,
 ) {
     let (movement_settings, mut hotbar) = query_player.single_mut();
@@ -246,20 +492,17 @@ fn player_scroll_inventory(
         return;
     }
 
-    let mut delta = 0.;
-    for event in scrolls.read() {
-        delta += event.y;
-    }
-    delta = delta.floor();
-
-    if delta == 0.0 {
-        return;
+    let slot_count = hotbar.slots.len();
+    if actions.just_pressed(Action::HotbarNext) {
+        hotbar.active_slot = (hotbar.active_slot + 1) % slot_count;
+    } else if actions.just_pressed(Action::HotbarPrevious) {
+        hotbar.active_slot = (hotbar.active_slot + slot_count - 1) % slot_count;
     }
 
-    if delta.is_sign_positive() {
-        hotbar.active_slot = (hotbar.active_slot + 1) % hotbar.slots.len();
-    } else if delta.is_sign_negative() {
-        hotbar.active_slot = (hotbar.active_slot + hotbar.slots.len() - 1) % hotbar.slots.len();
+    for slot in 0..slot_count.min(u8::MAX as usize) {
+        if actions.just_pressed(Action::HotbarSlot(slot as u8)) {
+            hotbar.active_slot = slot;
+        }
     }
 }
 
@@ -313,37 +556,170 @@ fn player_show_block_highlight(
     }
 }
 
-// TODO: Refactor to use TargetBlock
-pub fn player_break_place_block(
+/// Copies the targeted block into the hotbar, selecting it instead if it's already there. Survival
+/// players can only select blocks they already have.
+fn player_pick_block(
+    actions: Res<ActionState>,
+    metadata: Res<WorldMetadata>,
+    storage: Res<VoxelWorld>,
+    query_highlight: Query<(&TargetBlock, &Visibility), With<HoverHighlight>>,
+    mut query_player: Query<(&MovementSettings, &mut Hotbar), With<Player>>,
+) {
+    let (movement_settings, mut hotbar) = query_player.single_mut();
+    if !movement_settings.active || !actions.just_pressed(Action::Pick) {
+        return;
+    }
+
+    let (target, visibility) = query_highlight.single();
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+
+    // The target is only refreshed when it moves, so it may be stale if the block was replaced.
+    let [x, y, z] = target.local_pos;
+    let Some(block) = storage.get_voxel(&target.chunk_pos, x, y, z) else {
+        return;
+    };
+
+    let held = hotbar
+        .slots
+        .iter()
+        .position(|slot| slot.is_some_and(|stack| stack.block == block));
+    match (held, metadata.game_mode) {
+        (Some(slot), _) => hotbar.active_slot = slot,
+        (None, GameMode::Creative) => {
+            let active_slot = hotbar.active_slot;
+            hotbar.slots[active_slot] = Some(ItemStack::new(block, 1));
+        }
+        (None, GameMode::Survival) => {}
+    }
+}
+
+/// Builds up progress on breaking the targeted block while the break action is held, taking the
+/// block's `hardness` in seconds. Moving on to another block starts over.
+fn player_break_block(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    registry: Res<Registry>,
+    metadata: Res<WorldMetadata>,
+    mut storage: ResMut<VoxelWorld>,
+    mut drop_events: EventWriter<SpawnDrop>,
+    query_player: Query<&MovementSettings, With<Player>>,
+    mut query_highlight: Query<
+        (Ref<TargetBlock>, &Visibility, &mut BreakProgress),
+        With<HoverHighlight>,
+    >,
+    mut query_chunk: Query<(Entity, &mut Chunk)>,
+) {
+    let movement_settings = query_player.single();
+    let (target, visibility, mut progress) = query_highlight.single_mut();
+    if target.is_changed() {
+        progress.0 = 0.;
+    }
+
+    // The cursor is free for clicking on UI.
+    if !movement_settings.active
+        || !actions.pressed(Action::Break)
+        || *visibility == Visibility::Hidden
+    {
+        if progress.0 != 0. {
+            progress.0 = 0.;
+        }
+        return;
+    }
+
+    let air = registry.get_block_id("air");
+    let [local_x, local_y, local_z] = target.local_pos;
+    let Some(block) = storage
+        .get_voxel(&target.chunk_pos, local_x, local_y, local_z)
+        .filter(|&b| b != air)
+    else {
+        return;
+    };
+
+    let hardness = registry.get_block(block).hardness;
+    progress.0 += match hardness > 0. {
+        true => time.delta_secs() / hardness,
+        false => 1.,
+    };
+    if progress.0 < 1. {
+        return;
+    }
+    progress.0 = 0.;
+
+    storage.set_voxel(&target.chunk_pos, local_x, local_y, local_z, air);
+    if metadata.game_mode == GameMode::Survival {
+        drop_events.send(SpawnDrop {
+            stack: ItemStack::new(block, 1),
+            position: target.world_pos(storage.chunk_len).as_vec3() + Vec3::splat(0.5),
+        });
+    }
+
+    mark_block_changed(
+        &mut commands,
+        &storage,
+        &mut query_chunk,
+        target.chunk_pos,
+        target.local_pos,
+    );
+}
+
+/// Uses the targeted block if it has an interaction, otherwise places the held block against it.
+/// Crouching always places.
+pub fn player_place_block(
     mut commands: Commands,
-    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    actions: Res<ActionState>,
     registry: Res<Registry>,
+    metadata: Res<WorldMetadata>,
     mut storage: ResMut<VoxelWorld>,
     mut ray_cast: MeshRayCast,
-    query_player: Query<(&Hotbar, &Transform), With<Player>>,
+    mut interact_events: EventWriter<InteractWithBlock>,
+    mut block_entity_events: EventWriter<SpawnBlockEntity>,
+    mut query_player: Query<
+        (
+            Entity,
+            &mut Hotbar,
+            &Transform,
+            &MovementSettings,
+            &VoxelCollider,
+        ),
+        With<Player>,
+    >,
+    query_highlight: Query<(&TargetBlock, &Visibility), With<HoverHighlight>>,
     mut query_chunk: Query<(Entity, &mut Chunk)>,
 ) {
-    let (player_hotbar, player_transform) = query_player.single();
-
-    let (normal_multiplier, block_type, destroying) =
-        if mouse_buttons.just_pressed(MouseButton::Left) {
-            (-0.01, registry.get_block_id("air"), true)
-        } else if mouse_buttons.just_pressed(MouseButton::Right) {
-            let block = player_hotbar.slots[player_hotbar.active_slot];
-            if block.is_none() {
-                return;
-            }
-            (0.99, block.unwrap(), false)
-        } else {
+    let (player, mut player_hotbar, player_transform, movement_settings, collider) =
+        query_player.single_mut();
+    // The cursor is free for clicking on UI.
+    if !movement_settings.active || !actions.just_pressed(Action::Place) {
+        return;
+    }
+
+    let (target, visibility) = query_highlight.single();
+    if *visibility != Visibility::Hidden && !actions.pressed(Action::Crouch) {
+        // The target is only refreshed when it moves, so it may be stale if the block was replaced.
+        let position = target.world_pos(storage.chunk_len);
+        let interactive = storage
+            .get_block_at(position)
+            .is_some_and(|block| registry.get_block(block).interaction.is_some());
+        if interactive {
+            interact_events.send(InteractWithBlock { position, player });
             return;
-        };
+        }
+    }
+
+    let Some(block_type) = player_hotbar.active_block() else {
+        return;
+    };
+    let air = registry.get_block_id("air");
 
     let ray = Ray3d::new(player_transform.translation, player_transform.forward());
     let filter = |id| query_chunk.contains(id);
     let raycast_setings = RayCastSettings::default().with_filter(&filter);
 
     if let Some((_, hit)) = ray_cast.cast_ray(ray, &raycast_setings).first() {
-        let world_pos = (hit.point + hit.normal.normalize_or_zero() * normal_multiplier).floor();
+        let world_pos = (hit.point + hit.normal.normalize_or_zero() * 0.99).floor();
 
         let cx = (world_pos[0] / storage.chunk_len as f32).floor() as i32;
         let cy = (world_pos[1] / storage.chunk_len as f32).floor() as i32;
@@ -352,42 +728,65 @@ pub fn player_break_place_block(
         let local_y = (world_pos[1] as i32 - cy * storage.chunk_len as i32) as usize;
         let local_z = (world_pos[2] as i32 - cz * storage.chunk_len as i32) as usize;
 
-        if destroying
-            || storage
-                .get_voxel(&[cx, cy, cz], local_x, local_y, local_z)
-                .is_some_and(|b| b == registry.get_block_id("air"))
+        if storage
+            .get_voxel(&[cx, cy, cz], local_x, local_y, local_z)
+            .is_some_and(|b| b == air)
         {
             storage.set_voxel(&[cx, cy, cz], local_x, local_y, local_z, block_type);
 
-            let mut needs_meshing = vec![[cx, cy, cz]];
-            if local_x == 0 {
-                needs_meshing.push([cx - 1, cy, cz]);
-            }
-            if local_x == storage.chunk_len - 1 {
-                needs_meshing.push([cx + 1, cy, cz]);
-            }
-            if local_y == 0 {
-                needs_meshing.push([cx, cy - 1, cz]);
-            }
-            if local_y == storage.chunk_len - 1 {
-                needs_meshing.push([cx, cy + 1, cz]);
-            }
-            if local_z == 0 {
-                needs_meshing.push([cx, cy, cz - 1]);
-            }
-            if local_z == storage.chunk_len - 1 {
-                needs_meshing.push([cx, cy, cz + 1]);
+            // Don't wall players in while they're walking.
+            if !movement_settings.flying
+                && storage.overlaps(collider.aabb(player_transform.translation))
+            {
+                storage.set_voxel(&[cx, cy, cz], local_x, local_y, local_z, air);
+                return;
             }
 
-            for (id, mut chunk) in &mut query_chunk {
-                if chunk.world_pos == [cx, cy, cz] {
-                    chunk.dirty = true;
+            if metadata.game_mode == GameMode::Survival {
+                let active_slot = player_hotbar.active_slot;
+                let slot = &mut player_hotbar.slots[active_slot];
+                if let Some(stack) = slot {
+                    stack.count -= 1;
+                    if stack.count == 0 {
+                        *slot = None;
+                    }
                 }
+            }
 
-                if needs_meshing.contains(&chunk.world_pos) {
-                    commands.entity(id).insert(ChunkNeedsMeshing);
-                }
+            if registry.get_block(block_type).block_entity {
+                block_entity_events.send(SpawnBlockEntity {
+                    position: world_pos.as_ivec3(),
+                    data: BlockEntityData::default(),
+                });
             }
+
+            mark_block_changed(
+                &mut commands,
+                &storage,
+                &mut query_chunk,
+                [cx, cy, cz],
+                [local_x, local_y, local_z],
+            );
+        }
+    }
+}
+
+/// Flags the block's chunk for saving, and remeshes it along with any neighbours it touches.
+fn mark_block_changed(
+    commands: &mut Commands,
+    storage: &VoxelWorld,
+    query_chunk: &mut Query<(Entity, &mut Chunk)>,
+    chunk_pos: [i32; 3],
+    local_pos: [usize; 3],
+) {
+    let needs_meshing = storage.touched_chunks(chunk_pos, local_pos);
+    for (id, mut chunk) in query_chunk.iter_mut() {
+        if chunk.world_pos == chunk_pos {
+            chunk.dirty = true;
+        }
+
+        if needs_meshing.contains(&chunk.world_pos) {
+            commands.entity(id).insert(ChunkNeedsMeshing);
         }
     }
 }
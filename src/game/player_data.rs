@@ -0,0 +1,80 @@
+use std::{fs, io};
+
+use bevy::prelude::*;
+
+use crate::{assets::Registry, world::LoadError};
+
+use super::items::ItemStack;
+
+/// Identifies the local player's save, until there's more than one player.
+pub const LOCAL_PLAYER_ID: &str = "local";
+
+/// What's kept of a player between sessions, in `players/<id>.ron` in the world's save directory.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlayerData {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub speed: f32,
+    pub hotbar: Vec<Option<SavedStack>>,
+    pub active_slot: usize,
+    #[serde(default)]
+    pub inventory: Vec<Option<SavedStack>>,
+}
+
+/// An [`ItemStack`] by block identifier rather than id, which depends on asset load order. Saves
+/// from before stacks only have the identifier, which reads as a single block.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum SavedStack {
+    Stack(String, u32),
+    Single(String),
+}
+
+impl SavedStack {
+    pub fn from_stack(stack: &ItemStack, registry: &Registry) -> Self {
+        let identifier = registry.get_block(stack.block).identifier.clone();
+        Self::Stack(identifier, stack.count)
+    }
+
+    /// `None` for blocks that have since been removed.
+    pub fn resolve(&self, registry: &Registry) -> Option<ItemStack> {
+        let (identifier, count) = match self {
+            Self::Stack(identifier, count) => (identifier, *count),
+            Self::Single(identifier) => (identifier, 1),
+        };
+        let block = registry.find_block_id(identifier)?;
+        Some(ItemStack::new(block, count))
+    }
+}
+
+impl PlayerData {
+    /// Reads the player's save, or `None` if they haven't played in this world before.
+    pub fn load(save_dir: &str, id: &str) -> Result<Option<Self>, LoadError> {
+        let path = Self::path(save_dir, id);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(LoadError::Read(path, e)),
+        };
+        ron::from_str(&contents)
+            .map(Some)
+            .map_err(|e| LoadError::Parse(path, e.to_string()))
+    }
+
+    fn path(save_dir: &str, id: &str) -> String {
+        format!("{save_dir}/players/{id}.ron")
+    }
+
+    pub fn save(&self, save_dir: &str, id: &str) {
+        let dir = format!("{save_dir}/players");
+        if fs::create_dir_all(&dir).is_err() {
+            return;
+        }
+
+        let path = Self::path(save_dir, id);
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).unwrap();
+        if let Err(e) = fs::write(&path, contents) {
+            warn!("failed to write {path}: {e}");
+        }
+    }
+}
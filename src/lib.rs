@@ -1,4 +1,5 @@
 mod assets;
+mod controls;
 #[cfg(feature = "dev")]
 mod dev_tools;
 mod diagnostics;
@@ -71,6 +72,7 @@ impl Plugin for AppPlugin {
         // Add other plugins.
         app.add_plugins((
             assets::AssetsPlugin,
+            controls::ControlsPlugin,
             game::plugin,
             ui::UiPlugin,
             screens::plugin,
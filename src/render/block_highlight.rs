@@ -6,7 +6,7 @@ use bevy::{
 
 use crate::{
     assets::Registry,
-    game::player::{HoverHighlight, TargetBlock},
+    game::player::{BreakProgress, HoverHighlight, TargetBlock},
     screens::Screen,
 };
 
@@ -14,13 +14,32 @@ pub struct BlockHighlightPlugin;
 
 impl Plugin for BlockHighlightPlugin {
     fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(Screen::Gameplay), load_crack_materials);
         app.add_systems(
             Update,
-            update_highlight_mesh.run_if(in_state(Screen::Gameplay)),
+            (
+                update_highlight_mesh,
+                spawn_crack_overlay,
+                update_crack_mesh,
+                update_crack_stage,
+            )
+                .run_if(in_state(Screen::Gameplay)),
         );
     }
 }
 
+const CRACK_STAGES: usize = 10;
+/// Pushes the overlay off the block's faces so it doesn't z-fight with them.
+const CRACK_OFFSET: f32 = 0.002;
+
+/// One material per stage of breaking, from barely cracked to nearly broken.
+#[derive(Resource)]
+struct CrackMaterials(Vec<Handle<StandardMaterial>>);
+
+/// Shows how far through breaking the highlighted block the player is.
+#[derive(Component)]
+struct CrackOverlay;
+
 fn update_highlight_mesh(
     registry: Res<Registry>,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -57,3 +76,116 @@ fn update_highlight_mesh(
         hover_mesh.0 = new_mesh_handle;
     }
 }
+
+fn load_crack_materials(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let stages = (0..CRACK_STAGES)
+        .map(|stage| {
+            materials.add(StandardMaterial {
+                base_color_texture: Some(asset_server.load(format!("images/crack-{stage}.png"))),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })
+        })
+        .collect();
+    commands.insert_resource(CrackMaterials(stages));
+}
+
+fn spawn_crack_overlay(
+    mut commands: Commands,
+    crack_materials: Res<CrackMaterials>,
+    query_highlight: Query<Entity, Added<HoverHighlight>>,
+) {
+    for id in &query_highlight {
+        commands.entity(id).with_children(|parent| {
+            parent.spawn((
+                CrackOverlay,
+                Mesh3d::default(),
+                MeshMaterial3d(crack_materials.0[0].clone()),
+                Transform::default(),
+                Visibility::Hidden,
+                PickingBehavior::IGNORE,
+            ));
+        });
+    }
+}
+
+/// Covers the target's faces, so the cracks follow the shape of its model.
+fn update_crack_mesh(
+    registry: Res<Registry>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query_highlight: Query<(&TargetBlock, &Children), (With<HoverHighlight>, Changed<TargetBlock>)>,
+    mut query_overlay: Query<&mut Mesh3d, With<CrackOverlay>>,
+) {
+    let Ok((target, children)) = query_highlight.get_single() else {
+        return;
+    };
+    let Some(model_id) = target.model_id else {
+        return;
+    };
+
+    let model = registry.get_model(model_id);
+    let mut vs = vec![];
+    let mut ns = vec![];
+    let mut uvs = vec![];
+    let mut is = vec![];
+    for face in &model.faces {
+        let offset = vs.len() as u32;
+        let normal = Vec3::from_array(face.normal);
+        for v in &face.vertices {
+            vs.push((Vec3::from_array(v.position) + normal * CRACK_OFFSET).to_array());
+            ns.push(face.normal);
+            uvs.push(v.uv);
+        }
+        for j in 1..face.vertices.len().saturating_sub(1) as u32 {
+            is.extend([offset, offset + j, offset + j + 1]);
+        }
+    }
+
+    let new_mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vs)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, ns)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(is));
+    let new_mesh_handle = meshes.add(new_mesh);
+    for &child in children {
+        if let Ok(mut overlay_mesh) = query_overlay.get_mut(child) {
+            overlay_mesh.0 = new_mesh_handle.clone();
+        }
+    }
+}
+
+fn update_crack_stage(
+    crack_materials: Res<CrackMaterials>,
+    query_highlight: Query<(&BreakProgress, &Children), Changed<BreakProgress>>,
+    mut query_overlay: Query<
+        (&mut MeshMaterial3d<StandardMaterial>, &mut Visibility),
+        With<CrackOverlay>,
+    >,
+) {
+    let Ok((progress, children)) = query_highlight.get_single() else {
+        return;
+    };
+
+    let stage = ((progress.0 * CRACK_STAGES as f32) as usize).min(CRACK_STAGES - 1);
+    for &child in children {
+        let Ok((mut material, mut visibility)) = query_overlay.get_mut(child) else {
+            continue;
+        };
+
+        *visibility = match progress.0 > 0. {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        if material.0 != crack_materials.0[stage] {
+            material.0 = crack_materials.0[stage].clone();
+        }
+    }
+}
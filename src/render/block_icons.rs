@@ -0,0 +1,249 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    pbr::PreparedMaterial,
+    prelude::*,
+    render::{
+        camera::{RenderTarget, ScalingMode},
+        mesh::RenderMesh,
+        render_asset::RenderAssets,
+        render_resource::{
+            Extent3d, PipelineCache, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+        ExtractSchedule, MainWorld, RenderApp,
+    },
+};
+
+use crate::{assets::Registry, screens::Screen};
+
+use super::chunk_mesh::{quad_mesh, ArrayTextureMaterial};
+
+pub struct BlockIconsPlugin;
+
+impl Plugin for BlockIconsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(Screen::Gameplay), render_icons);
+        app.add_systems(
+            Update,
+            finish_rendering_icons.run_if(in_state(Screen::Gameplay)),
+        );
+
+        // Only the render world knows when everything the icons need has reached the GPU.
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(ExtractSchedule, check_icons_ready);
+        }
+    }
+}
+
+/// Pixel size of a single icon in the atlas.
+const ICON_SIZE: u32 = 64;
+/// Keeps the icon scene out of every other camera's view.
+const ICON_LAYER: usize = 1;
+/// Size of a block relative to its icon. An isometric cube is about 1.6 blocks tall.
+const ICON_SCALE: f32 = 0.6;
+/// Frames the icon camera keeps rendering once everything is ready, to be sure a whole frame has
+/// been drawn with it.
+const ICON_SETTLE_FRAMES: u32 = 2;
+
+/// Isometric renders of every block with a model, packed into a single atlas.
+#[derive(Resource)]
+pub struct BlockIcons {
+    pub atlas: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    /// Atlas index of each block's icon, by block id.
+    indices: Vec<Option<usize>>,
+}
+
+impl BlockIcons {
+    pub fn image_node(&self, block_id: usize) -> Option<ImageNode> {
+        let index = self.indices.get(block_id).copied().flatten()?;
+        Some(ImageNode::from_atlas_image(
+            self.atlas.clone(),
+            TextureAtlas {
+                layout: self.layout.clone(),
+                index,
+            },
+        ))
+    }
+}
+
+/// Frames rendered since the icons became ready.
+#[derive(Component, Default)]
+struct IconCamera(u32);
+
+/// What has to be on the GPU before the icons can be drawn. The material's pipeline is compiled in
+/// the background and its array texture uploaded whenever it's loaded, so this can take a while
+/// on a cold start.
+#[derive(Resource)]
+struct IconRenderState {
+    material: AssetId<ArrayTextureMaterial>,
+    meshes: Vec<AssetId<Mesh>>,
+    ready: bool,
+}
+
+#[derive(Component)]
+struct IconScene;
+
+fn render_icons(
+    mut commands: Commands,
+    registry: Res<Registry>,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ArrayTextureMaterial>>,
+) {
+    let mut indices = vec![None; registry.block_count()];
+    let mut icon_count = 0;
+    for (block_id, index) in indices.iter_mut().enumerate() {
+        if registry.get_block(block_id).model.is_some() {
+            *index = Some(icon_count);
+            icon_count += 1;
+        }
+    }
+
+    let columns = (icon_count as f32).sqrt().ceil().max(1.) as u32;
+    let rows = (icon_count as u32).div_ceil(columns).max(1);
+
+    let size = Extent3d {
+        width: columns * ICON_SIZE,
+        height: rows * ICON_SIZE,
+        ..default()
+    };
+    let mut atlas = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    atlas.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    let atlas = images.add(atlas);
+    let layout = layouts.add(TextureAtlasLayout::from_grid(
+        UVec2::splat(ICON_SIZE),
+        columns,
+        rows,
+        None,
+        None,
+    ));
+
+    // One world unit per icon, with the first in the top left corner.
+    commands.spawn((
+        StateScoped(Screen::Gameplay),
+        IconCamera::default(),
+        Camera3d::default(),
+        Camera {
+            target: RenderTarget::Image(atlas.clone()),
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            order: -1,
+            ..default()
+        },
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed {
+                width: columns as f32,
+                height: rows as f32,
+            },
+            ..OrthographicProjection::default_3d()
+        }),
+        Transform::from_xyz(columns as f32 / 2., -(rows as f32) / 2., 10.),
+        RenderLayers::layer(ICON_LAYER),
+    ));
+
+    let material = materials.add(ArrayTextureMaterial {
+        array_texture: registry.block_array_texture.clone(),
+    });
+    let rotation =
+        Quat::from_rotation_x(30f32.to_radians()) * Quat::from_rotation_y(-45f32.to_radians());
+    let mut mesh_ids = vec![];
+    for (block_id, index) in indices.iter().enumerate() {
+        let Some(index) = index else {
+            continue;
+        };
+
+        let block = registry.get_block(block_id);
+        let model = registry.get_model(block.model.unwrap());
+        let (mut vs, mut ns, mut uvs, mut ts) = (vec![], vec![], vec![], vec![]);
+        model.mesh(
+            &[false; 6],
+            &[-0.5, -0.5, -0.5],
+            &mut vs,
+            &mut ns,
+            &mut uvs,
+            &mut ts,
+            block,
+        );
+
+        let column = (*index as u32 % columns) as f32;
+        let row = (*index as u32 / columns) as f32;
+        let mesh = meshes.add(quad_mesh(vs, ns, uvs, ts));
+        mesh_ids.push(mesh.id());
+        commands.spawn((
+            StateScoped(Screen::Gameplay),
+            IconScene,
+            Mesh3d(mesh),
+            MeshMaterial3d(material.clone()),
+            Transform::from_xyz(column + 0.5, -(row + 0.5), 0.)
+                .with_rotation(rotation)
+                .with_scale(Vec3::splat(ICON_SCALE)),
+            RenderLayers::layer(ICON_LAYER),
+        ));
+    }
+
+    commands.insert_resource(IconRenderState {
+        material: material.id(),
+        meshes: mesh_ids,
+        ready: false,
+    });
+    commands.insert_resource(BlockIcons {
+        atlas,
+        layout,
+        indices,
+    });
+}
+
+/// Runs in the render world, which can't be reached from the main world's systems.
+fn check_icons_ready(
+    mut main_world: ResMut<MainWorld>,
+    pipelines: Res<PipelineCache>,
+    materials: Res<RenderAssets<PreparedMaterial<ArrayTextureMaterial>>>,
+    meshes: Res<RenderAssets<RenderMesh>>,
+) {
+    let Some(mut state) = main_world.get_resource_mut::<IconRenderState>() else {
+        return;
+    };
+
+    // Pipelines are only queued once the material is prepared, so that has to come first.
+    let ready = materials.get(state.material).is_some()
+        && state.meshes.iter().all(|&id| meshes.get(id).is_some())
+        && pipelines.waiting_pipelines().next().is_none();
+    if state.ready != ready {
+        state.ready = ready;
+    }
+}
+
+/// The atlas keeps what was last rendered to it, so the scene can go once the icons are done.
+fn finish_rendering_icons(
+    mut commands: Commands,
+    state: Option<Res<IconRenderState>>,
+    mut query_camera: Query<(Entity, &mut IconCamera)>,
+    query_scene: Query<Entity, With<IconScene>>,
+) {
+    let Ok((camera_id, mut camera)) = query_camera.get_single_mut() else {
+        return;
+    };
+
+    if !state.is_some_and(|state| state.ready) {
+        return;
+    }
+
+    camera.0 += 1;
+    if camera.0 <= ICON_SETTLE_FRAMES {
+        return;
+    }
+
+    commands.entity(camera_id).despawn_recursive();
+    for id in &query_scene {
+        commands.entity(id).despawn_recursive();
+    }
+    commands.remove_resource::<IconRenderState>();
+}
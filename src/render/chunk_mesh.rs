@@ -12,9 +12,9 @@ use bevy::{
 
 use crate::{
     assets::Registry,
-    diagnostics::MESHING_TIME_DIAGNOSTIC,
+    diagnostics::{MESHING_COUNT_DIAGNOSTIC, MESHING_FRAME_DIAGNOSTIC, MESHING_TIME_DIAGNOSTIC},
     screens::Screen,
-    world::{Chunk, VoxelWorld},
+    world::{Chunk, ChunkBudget, VoxelWorld},
     AppSet,
 };
 
@@ -71,9 +71,36 @@ impl Material for ArrayTextureMaterial {
     }
 }
 
+/// Builds a mesh for [`ArrayTextureMaterial`] out of quads, as written by [`Model::mesh`].
+///
+/// [`Model::mesh`]: crate::assets::Model::mesh
+pub fn quad_mesh(vs: Vec<[f32; 3]>, ns: Vec<[f32; 3]>, uvs: Vec<[f32; 2]>, ts: Vec<u32>) -> Mesh {
+    let quad_count = vs.len() / 4;
+    let mut is = Vec::with_capacity(6 * quad_count);
+    for i in 0..quad_count as u32 {
+        is.push(i * 4);
+        is.push(i * 4 + 1);
+        is.push(i * 4 + 2);
+        is.push(i * 4);
+        is.push(i * 4 + 2);
+        is.push(i * 4 + 3);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vs)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, ns)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_attribute(ATTRIBUTE_TEXTURE, ts)
+    .with_inserted_indices(Indices::U32(is))
+}
+
 fn sys_chunk_mesher(
     mut commands: Commands,
     mut diagnostics: Diagnostics,
+    budget: Res<ChunkBudget>,
     registry: Res<Registry>,
     storage: Res<VoxelWorld>,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -93,11 +120,10 @@ fn sys_chunk_mesher(
         [0, 0, -1], // back
     ];
 
-    let mut chunk_count = 0;
+    let mut budget_timer = budget.meshing.start(budget.phase);
     for (id, chunk, _) in &chunks_query {
-        // TODO: Make chunks per frame configurable
-        if chunk_count > 8 {
-            return;
+        if budget_timer.exhausted() {
+            break;
         }
 
         let start_time = Instant::now();
@@ -190,27 +216,8 @@ fn sys_chunk_mesher(
             }
         }
 
-        let quad_count = vs.len() / 4;
-        let mut is = Vec::with_capacity(6 * quad_count);
-        for i in 0..quad_count as u32 {
-            is.push(i * 4);
-            is.push(i * 4 + 1);
-            is.push(i * 4 + 2);
-            is.push(i * 4);
-            is.push(i * 4 + 2);
-            is.push(i * 4 + 3);
-        }
-
         if !vs.is_empty() {
-            let mesh = Mesh::new(
-                PrimitiveTopology::TriangleList,
-                RenderAssetUsages::default(),
-            )
-            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vs)
-            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, ns)
-            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
-            .with_inserted_attribute(ATTRIBUTE_TEXTURE, ts)
-            .with_inserted_indices(Indices::U32(is));
+            let mesh = quad_mesh(vs, ns, uvs, ts);
             commands.entity(id).remove::<ChunkNeedsMeshing>().insert((
                 Mesh3d(meshes.add(mesh)),
                 MeshMaterial3d(material_handle.clone()),
@@ -222,6 +229,12 @@ fn sys_chunk_mesher(
 
         let mesh_time = (Instant::now() - start_time).as_micros();
         diagnostics.add_measurement(&MESHING_TIME_DIAGNOSTIC, || mesh_time as f64);
-        chunk_count += 1;
+        budget_timer.record_chunk();
     }
+
+    budget_timer.report(
+        &mut diagnostics,
+        &MESHING_FRAME_DIAGNOSTIC,
+        &MESHING_COUNT_DIAGNOSTIC,
+    );
 }
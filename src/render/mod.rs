@@ -1,11 +1,15 @@
 mod block_highlight;
+mod block_icons;
 mod chunk_mesh;
 mod sky;
 mod wireframe;
 
 use bevy::prelude::*;
 
-pub use chunk_mesh::ChunkNeedsMeshing;
+pub use {
+    block_icons::BlockIcons,
+    chunk_mesh::{quad_mesh, ArrayTextureMaterial, ChunkNeedsMeshing},
+};
 
 pub struct RenderPlugin;
 
@@ -16,6 +20,7 @@ impl Plugin for RenderPlugin {
             chunk_mesh::ChunkMeshPlugin,
             wireframe::WireframePlugin,
             block_highlight::BlockHighlightPlugin,
+            block_icons::BlockIconsPlugin,
         ));
     }
 }
@@ -3,6 +3,7 @@ use bevy::prelude::*;
 use crate::{
     assets::Registry,
     game::player::{Hotbar, Player},
+    render::BlockIcons,
     screens::Screen,
 };
 
@@ -11,69 +12,215 @@ pub struct HotbarUiPlugin;
 impl Plugin for HotbarUiPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(Screen::Gameplay), setup);
-        app.add_systems(Update, update_ui.run_if(in_state(Screen::Gameplay)));
+        app.add_systems(
+            Update,
+            (rebuild_slots, select_clicked_slot, update_ui)
+                .chain()
+                .run_if(in_state(Screen::Gameplay)),
+        );
     }
 }
 
+const ACTIVE_SLOT_BORDER: Color = Color::WHITE;
+const INACTIVE_SLOT_BORDER: Color = Color::NONE;
+
 #[derive(Component)]
 pub struct HotbarUi;
 
+#[derive(Component)]
+pub struct HotbarLabel;
+
+#[derive(Component)]
+pub struct HotbarSlots;
+
+#[derive(Component)]
+pub struct HotbarSlot(pub usize);
+
+#[derive(Component)]
+pub struct HotbarSlotIcon(pub usize);
+
+#[derive(Component)]
+pub struct HotbarSlotCount(pub usize);
+
 fn setup(mut commands: Commands) {
-    commands.spawn((
-        StateScoped(Screen::Gameplay),
-        HotbarUi,
-        Node {
-            width: Val::Percent(100.0),
-            height: Val::Percent(100.0),
-            align_items: AlignItems::Center,
-            justify_content: JustifyContent::End,
-            flex_direction: FlexDirection::Column,
-            row_gap: Val::Px(16.0),
-            padding: UiRect::all(Val::Px(16.0)),
-            position_type: PositionType::Absolute,
-            ..default()
-        },
-    ));
+    commands
+        .spawn((
+            StateScoped(Screen::Gameplay),
+            HotbarUi,
+            // Stays above the inventory, so blocks can be assigned to any slot.
+            GlobalZIndex(1),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::End,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(16.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((HotbarLabel, Text::default()));
+            parent.spawn((
+                HotbarSlots,
+                Node {
+                    align_items: AlignItems::End,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(8.0),
+                    ..default()
+                },
+            ));
+        });
 }
 
-// TODO: Put models of the item
-fn update_ui(
+/// Only respawns the slots when their number changes. Everything else is updated in place.
+fn rebuild_slots(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    registry: Res<Registry>,
-    query_hotbar_ui: Query<Entity, With<HotbarUi>>,
+    query_slots_ui: Query<(Entity, Option<&Children>), With<HotbarSlots>>,
     query_player: Query<&Hotbar, (With<Player>, Changed<Hotbar>)>,
 ) {
-    let hotbar_ui_id = query_hotbar_ui.single();
+    let (slots_ui_id, children) = query_slots_ui.single();
     let Ok(hotbar) = query_player.get_single() else {
         return;
     };
 
+    if children.map_or(0, |children| children.len()) == hotbar.slots.len() {
+        return;
+    }
+
     let slot_image = asset_server.load("images/hotbar-slot.png");
     commands
-        .entity(hotbar_ui_id)
+        .entity(slots_ui_id)
         .despawn_descendants()
         .with_children(|parent| {
-            if let Some(block_id) = hotbar.slots[hotbar.active_slot] {
-                let block = registry.get_block(block_id);
-                parent.spawn(Text::new(&block.identifier));
-            }
-
-            parent
-                .spawn(Node {
-                    align_items: AlignItems::End,
-                    justify_content: JustifyContent::Center,
-                    flex_direction: FlexDirection::Row,
-                    column_gap: Val::Px(8.0),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    for _ in 0..hotbar.slots.len() {
-                        parent.spawn(ImageNode {
-                            image: slot_image.clone(),
+            for i in 0..hotbar.slots.len() {
+                parent
+                    .spawn((
+                        HotbarSlot(i),
+                        Button,
+                        Node {
+                            border: UiRect::all(Val::Px(2.0)),
                             ..default()
-                        });
-                    }
-                });
+                        },
+                        BorderColor(INACTIVE_SLOT_BORDER),
+                    ))
+                    .with_children(|parent| {
+                        parent
+                            .spawn(ImageNode {
+                                image: slot_image.clone(),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    HotbarSlotIcon(i),
+                                    ImageNode::default(),
+                                    Node {
+                                        position_type: PositionType::Absolute,
+                                        width: Val::Percent(100.0),
+                                        height: Val::Percent(100.0),
+                                        ..default()
+                                    },
+                                    Visibility::Hidden,
+                                ));
+                                parent.spawn((
+                                    HotbarSlotCount(i),
+                                    Text::default(),
+                                    TextFont::from_font_size(12.0),
+                                    Node {
+                                        position_type: PositionType::Absolute,
+                                        right: Val::Px(2.0),
+                                        bottom: Val::Px(0.0),
+                                        ..default()
+                                    },
+                                ));
+                            });
+                    });
+            }
         });
 }
+
+/// Only reachable with the cursor free, like while the inventory is open.
+fn select_clicked_slot(
+    query_slots: Query<(&HotbarSlot, &Interaction), Changed<Interaction>>,
+    mut query_player: Query<&mut Hotbar, With<Player>>,
+) {
+    for (slot, interaction) in &query_slots {
+        if *interaction == Interaction::Pressed {
+            query_player.single_mut().active_slot = slot.0;
+        }
+    }
+}
+
+fn update_ui(
+    registry: Res<Registry>,
+    icons: Option<Res<BlockIcons>>,
+    mut query_label: Query<&mut Text, With<HotbarLabel>>,
+    mut query_slots: Query<(&HotbarSlot, &mut BorderColor)>,
+    mut query_icons: Query<(Ref<HotbarSlotIcon>, &mut ImageNode, &mut Visibility)>,
+    mut query_counts: Query<(Ref<HotbarSlotCount>, &mut Text), Without<HotbarLabel>>,
+    query_player: Query<Ref<Hotbar>, With<Player>>,
+) {
+    let Ok(hotbar) = query_player.get_single() else {
+        return;
+    };
+
+    // Checked every frame so freshly spawned slots pick up the highlight too.
+    let mut label = query_label.single_mut();
+    for (slot, mut border) in &mut query_slots {
+        let color = match slot.0 == hotbar.active_slot {
+            true => ACTIVE_SLOT_BORDER,
+            false => INACTIVE_SLOT_BORDER,
+        };
+        if border.0 != color {
+            border.0 = color;
+        }
+    }
+
+    let icons_changed = icons.as_ref().is_some_and(|icons| icons.is_changed());
+    for (slot, mut image, mut visibility) in &mut query_icons {
+        if !hotbar.is_changed() && !icons_changed && !slot.is_added() {
+            continue;
+        }
+
+        let icon = hotbar
+            .slots
+            .get(slot.0)
+            .copied()
+            .flatten()
+            .map(|stack| stack.block)
+            .zip(icons.as_ref())
+            .and_then(|(block_id, icons)| icons.image_node(block_id));
+        match icon {
+            Some(icon) => {
+                *image = icon;
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    // Creative's single blocks don't need a count.
+    for (slot, mut text) in &mut query_counts {
+        if !hotbar.is_changed() && !slot.is_added() {
+            continue;
+        }
+
+        text.0 = match hotbar.slots.get(slot.0).copied().flatten() {
+            Some(stack) if stack.count > 1 => stack.count.to_string(),
+            _ => String::new(),
+        };
+    }
+
+    if !hotbar.is_changed() {
+        return;
+    }
+
+    label.0 = match hotbar.active_block() {
+        Some(block_id) => registry.get_block(block_id).identifier.clone(),
+        None => String::new(),
+    };
+}
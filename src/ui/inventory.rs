@@ -0,0 +1,502 @@
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    assets::Registry,
+    controls::{Action, ActionState, KeyboardCapture},
+    game::{
+        items::{Inventory, ItemStack, INVENTORY_SLOTS},
+        player::{set_player_control, Hotbar, MovementSettings, Player},
+    },
+    render::BlockIcons,
+    screens::Screen,
+    world::{GameMode, WorldMetadata, WorldSetup},
+};
+
+use super::theme::palette::{BUTTON_HOVERED_BACKGROUND, LABEL_TEXT, NODE_BACKGROUND};
+
+pub struct InventoryUiPlugin;
+
+impl Plugin for InventoryUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InventorySearch>();
+        app.add_systems(OnEnter(Screen::Gameplay), setup.after(WorldSetup));
+        app.add_systems(
+            Update,
+            (
+                populate_entries.run_if(resource_added::<BlockIcons>),
+                update_slots,
+                toggle_inventory,
+                (
+                    focus_search,
+                    type_search,
+                    filter_entries,
+                    highlight_entries,
+                    highlight_slots,
+                    assign_clicked_block,
+                    swap_clicked_slot,
+                )
+                    .chain()
+                    .run_if(resource_exists::<InventoryOpen>),
+            )
+                .chain()
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+const ENTRY_SIZE: f32 = 56.0;
+const OVERLAY_BACKGROUND: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const PANEL_BACKGROUND: Color = Color::srgba(0.1, 0.1, 0.1, 0.9);
+const SLOT_BACKGROUND: Color = Color::srgba(1.0, 1.0, 1.0, 0.1);
+const SEARCH_PLACEHOLDER: &str = "Search...";
+
+/// Present while the inventory is open. The player doesn't have control in the meantime.
+#[derive(Resource)]
+pub struct InventoryOpen;
+
+#[derive(Resource, Default)]
+struct InventorySearch {
+    text: String,
+    /// Whether keys go to the search box rather than triggering actions.
+    focused: bool,
+}
+
+#[derive(Component)]
+struct InventoryUi;
+
+#[derive(Component)]
+struct InventorySearchBox;
+
+#[derive(Component)]
+struct InventorySearchText;
+
+#[derive(Component)]
+struct InventoryHoverLabel;
+
+#[derive(Component)]
+struct InventoryGrid;
+
+/// A block in creative's list of every block.
+#[derive(Component)]
+struct InventoryEntry(usize);
+
+/// One of survival's inventory slots.
+#[derive(Component)]
+struct InventorySlot(usize);
+
+#[derive(Component)]
+struct InventorySlotIcon(usize);
+
+#[derive(Component)]
+struct InventorySlotCount(usize);
+
+/// Creative lists every block, while survival shows what the player is carrying.
+fn setup(mut commands: Commands, metadata: Res<WorldMetadata>) {
+    commands
+        .spawn((
+            StateScoped(Screen::Gameplay),
+            InventoryUi,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            BackgroundColor(OVERLAY_BACKGROUND),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Percent(60.0),
+                        max_height: Val::Percent(60.0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(8.0),
+                        padding: UiRect::all(Val::Px(16.0)),
+                        ..default()
+                    },
+                    BackgroundColor(PANEL_BACKGROUND),
+                ))
+                .with_children(|parent| {
+                    match metadata.game_mode {
+                        GameMode::Creative => spawn_creative_panel(parent),
+                        GameMode::Survival => spawn_survival_panel(parent),
+                    }
+                    parent.spawn((InventoryHoverLabel, Text::default(), TextColor(LABEL_TEXT)));
+                });
+        });
+}
+
+fn spawn_creative_panel(parent: &mut ChildBuilder) {
+    parent
+        .spawn((
+            InventorySearchBox,
+            Button,
+            Node {
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(NODE_BACKGROUND),
+        ))
+        .with_children(|parent| {
+            parent.spawn((InventorySearchText, Text::new(SEARCH_PLACEHOLDER)));
+        });
+    parent.spawn((
+        InventoryGrid,
+        Node {
+            flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::Wrap,
+            column_gap: Val::Px(4.0),
+            row_gap: Val::Px(4.0),
+            overflow: Overflow::scroll_y(),
+            ..default()
+        },
+    ));
+}
+
+fn spawn_survival_panel(parent: &mut ChildBuilder) {
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::Wrap,
+            column_gap: Val::Px(4.0),
+            row_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            for i in 0..INVENTORY_SLOTS {
+                parent
+                    .spawn((
+                        InventorySlot(i),
+                        Button,
+                        Node {
+                            width: Val::Px(ENTRY_SIZE),
+                            height: Val::Px(ENTRY_SIZE),
+                            padding: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(SLOT_BACKGROUND),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            InventorySlotIcon(i),
+                            ImageNode::default(),
+                            Node {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            Visibility::Hidden,
+                        ));
+                        parent.spawn((
+                            InventorySlotCount(i),
+                            Text::default(),
+                            TextFont::from_font_size(14.0),
+                            Node {
+                                position_type: PositionType::Absolute,
+                                right: Val::Px(4.0),
+                                bottom: Val::Px(2.0),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+/// Every block with an icon gets an entry, which leaves out air.
+fn populate_entries(
+    mut commands: Commands,
+    registry: Res<Registry>,
+    icons: Res<BlockIcons>,
+    query_grid: Query<Entity, With<InventoryGrid>>,
+) {
+    let Ok(grid_id) = query_grid.get_single() else {
+        return;
+    };
+
+    commands
+        .entity(grid_id)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for block_id in 0..registry.block_count() {
+                let Some(icon) = icons.image_node(block_id) else {
+                    continue;
+                };
+
+                parent
+                    .spawn((
+                        InventoryEntry(block_id),
+                        Button,
+                        Node {
+                            width: Val::Px(ENTRY_SIZE),
+                            height: Val::Px(ENTRY_SIZE),
+                            padding: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::NONE),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            icon,
+                            Node {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn toggle_inventory(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    open: Option<Res<InventoryOpen>>,
+    mut search: ResMut<InventorySearch>,
+    mut keyboard_capture: ResMut<KeyboardCapture>,
+    mut query_window: Query<&mut Window, With<PrimaryWindow>>,
+    mut query_player: Query<&mut MovementSettings, With<Player>>,
+    mut query_ui: Query<&mut Visibility, With<InventoryUi>>,
+) {
+    let opening = match open {
+        Some(_) => {
+            let closing = actions.just_pressed(Action::ToggleInventory)
+                || actions.just_pressed(Action::ToggleControl);
+            if !closing {
+                return;
+            }
+            false
+        }
+        None => {
+            if !actions.just_pressed(Action::ToggleInventory) {
+                return;
+            }
+            true
+        }
+    };
+
+    match opening {
+        true => commands.insert_resource(InventoryOpen),
+        false => commands.remove_resource::<InventoryOpen>(),
+    }
+    search.focused = false;
+    keyboard_capture.0 = false;
+
+    *query_ui.single_mut() = match opening {
+        true => Visibility::Inherited,
+        false => Visibility::Hidden,
+    };
+    set_player_control(
+        &mut query_player.single_mut(),
+        &mut query_window.single_mut(),
+        !opening,
+    );
+}
+
+fn focus_search(
+    mut search: ResMut<InventorySearch>,
+    mut keyboard_capture: ResMut<KeyboardCapture>,
+    query_search_box: Query<&Interaction, (With<InventorySearchBox>, Changed<Interaction>)>,
+) {
+    if query_search_box
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        search.focused = true;
+        keyboard_capture.0 = true;
+    }
+}
+
+fn type_search(
+    mut search: ResMut<InventorySearch>,
+    mut keyboard_capture: ResMut<KeyboardCapture>,
+    mut keyboard_inputs: EventReader<KeyboardInput>,
+    mut query_text: Query<&mut Text, With<InventorySearchText>>,
+) {
+    if !search.focused {
+        keyboard_inputs.clear();
+        return;
+    }
+
+    for event in keyboard_inputs.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(chars) => search.text.push_str(chars),
+            Key::Space => search.text.push(' '),
+            Key::Backspace => {
+                search.text.pop();
+            }
+            Key::Escape | Key::Enter => {
+                search.focused = false;
+                keyboard_capture.0 = false;
+            }
+            _ => {}
+        }
+    }
+
+    if search.is_changed() {
+        query_text.single_mut().0 = match (search.text.is_empty(), search.focused) {
+            (true, false) => SEARCH_PLACEHOLDER.to_string(),
+            (_, true) => format!("{}_", search.text),
+            (false, false) => search.text.clone(),
+        };
+    }
+}
+
+fn filter_entries(
+    registry: Res<Registry>,
+    search: Res<InventorySearch>,
+    mut query_entries: Query<(&InventoryEntry, &mut Node)>,
+) {
+    if !search.is_changed() {
+        return;
+    }
+
+    let needle = search.text.trim().to_lowercase();
+    for (entry, mut node) in &mut query_entries {
+        let identifier = &registry.get_block(entry.0).identifier;
+        node.display = match identifier.to_lowercase().contains(&needle) {
+            true => Display::Flex,
+            false => Display::None,
+        };
+    }
+}
+
+fn highlight_entries(
+    registry: Res<Registry>,
+    mut query_entries: Query<
+        (&InventoryEntry, &Interaction, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut query_label: Query<&mut Text, With<InventoryHoverLabel>>,
+) {
+    let mut label = query_label.single_mut();
+    for (entry, interaction, mut background) in &mut query_entries {
+        match interaction {
+            Interaction::None => {
+                background.0 = Color::NONE;
+                label.0.clear();
+            }
+            Interaction::Hovered | Interaction::Pressed => {
+                background.0 = BUTTON_HOVERED_BACKGROUND;
+                label.0 = registry.get_block(entry.0).identifier.clone();
+            }
+        }
+    }
+}
+
+/// Clicking a block puts it in the active hotbar slot.
+fn assign_clicked_block(
+    query_entries: Query<(&InventoryEntry, &Interaction), Changed<Interaction>>,
+    mut query_player: Query<&mut Hotbar, With<Player>>,
+) {
+    for (entry, interaction) in &query_entries {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let mut hotbar = query_player.single_mut();
+        let active_slot = hotbar.active_slot;
+        hotbar.slots[active_slot] = Some(ItemStack::new(entry.0, 1));
+    }
+}
+
+fn update_slots(
+    icons: Option<Res<BlockIcons>>,
+    query_player: Query<Ref<Inventory>, With<Player>>,
+    mut query_icons: Query<(Ref<InventorySlotIcon>, &mut ImageNode, &mut Visibility)>,
+    mut query_counts: Query<(Ref<InventorySlotCount>, &mut Text)>,
+) {
+    let Ok(inventory) = query_player.get_single() else {
+        return;
+    };
+
+    let icons_changed = icons.as_ref().is_some_and(|icons| icons.is_changed());
+    for (slot, mut image, mut visibility) in &mut query_icons {
+        if !inventory.is_changed() && !icons_changed && !slot.is_added() {
+            continue;
+        }
+
+        let icon = inventory.slots[slot.0]
+            .map(|stack| stack.block)
+            .zip(icons.as_ref())
+            .and_then(|(block_id, icons)| icons.image_node(block_id));
+        match icon {
+            Some(icon) => {
+                *image = icon;
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    for (slot, mut text) in &mut query_counts {
+        if !inventory.is_changed() && !slot.is_added() {
+            continue;
+        }
+
+        text.0 = match inventory.slots[slot.0] {
+            Some(stack) if stack.count > 1 => stack.count.to_string(),
+            _ => String::new(),
+        };
+    }
+}
+
+fn highlight_slots(
+    registry: Res<Registry>,
+    mut query_slots: Query<
+        (&InventorySlot, &Interaction, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    query_player: Query<&Inventory, With<Player>>,
+    mut query_label: Query<&mut Text, With<InventoryHoverLabel>>,
+) {
+    let inventory = query_player.single();
+    let mut label = query_label.single_mut();
+    for (slot, interaction, mut background) in &mut query_slots {
+        match interaction {
+            Interaction::None => {
+                background.0 = SLOT_BACKGROUND;
+                label.0.clear();
+            }
+            Interaction::Hovered | Interaction::Pressed => {
+                background.0 = BUTTON_HOVERED_BACKGROUND;
+                label.0 = match inventory.slots[slot.0] {
+                    Some(stack) => registry.get_block(stack.block).identifier.clone(),
+                    None => String::new(),
+                };
+            }
+        }
+    }
+}
+
+/// Clicking an inventory slot swaps it with the active hotbar slot.
+fn swap_clicked_slot(
+    query_slots: Query<(&InventorySlot, &Interaction), Changed<Interaction>>,
+    mut query_player: Query<(&mut Hotbar, &mut Inventory), With<Player>>,
+) {
+    for (slot, interaction) in &query_slots {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let (mut hotbar, mut inventory) = query_player.single_mut();
+        let active_slot = hotbar.active_slot;
+        std::mem::swap(&mut hotbar.slots[active_slot], &mut inventory.slots[slot.0]);
+    }
+}
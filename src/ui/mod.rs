@@ -1,12 +1,15 @@
 mod crosshair;
 mod debug_stats;
 mod hotbar;
+mod inventory;
 mod loading_screen;
 mod splash_screen;
 mod theme;
 
 use bevy::prelude::*;
 
+pub use inventory::InventoryOpen;
+
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
@@ -17,6 +20,7 @@ impl Plugin for UiPlugin {
             crosshair::CrosshairUiPlugin,
             theme::plugin,
             hotbar::HotbarUiPlugin,
+            inventory::InventoryUiPlugin,
             debug_stats::DebugStatsUiPlugin,
         ));
     }
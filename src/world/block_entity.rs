@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+
+use crate::{assets::Registry, screens::Screen};
+
+use super::{
+    chunk::{Chunk, ChunkNeedsGenerating, ChunkNeedsLoading},
+    loader::LoadedChunks,
+    voxel_world::VoxelWorld,
+};
+
+pub struct BlockEntityPlugin;
+
+impl Plugin for BlockEntityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnBlockEntity>();
+        app.add_event::<BlockEntityRemoved>();
+        app.add_systems(
+            Update,
+            (
+                spawn_block_entities,
+                despawn_orphaned_block_entities,
+                mark_changed_block_entities,
+            )
+                .chain()
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+/// Extra data for the block at `position`. Block entities are children of their chunk's entity,
+/// so they're saved, loaded and unloaded along with it.
+#[derive(Component)]
+pub struct BlockEntity {
+    pub position: IVec3,
+}
+
+/// Blocks generated by the world, rather than placed, start out without any of this. Anything
+/// reading it should be fine with it missing.
+#[derive(Component, Debug, Clone, Default, bitcode::Encode, bitcode::Decode)]
+pub struct BlockEntityData {
+    pub text: Option<String>,
+    /// Block identifier and count of every stack, since ids depend on asset load order.
+    pub inventory: Option<Vec<Option<(String, u32)>>>,
+    /// Seconds.
+    pub timer: Option<f32>,
+}
+
+/// How block entities are written into a chunk's save, by position within the chunk.
+pub type SavedBlockEntities = Vec<([usize; 3], BlockEntityData)>;
+
+/// Attaches data to the block at `position`, replacing anything already there. Held back while
+/// the chunk is still being loaded or generated, and dropped if it isn't loaded at all.
+#[derive(Event, Clone)]
+pub struct SpawnBlockEntity {
+    pub position: IVec3,
+    pub data: BlockEntityData,
+}
+
+/// Sent when a block entity goes because its block did, with whatever it held, so nothing it
+/// stored vanishes along with it. Not sent for block entities unloaded with their chunk.
+#[derive(Event)]
+pub struct BlockEntityRemoved {
+    pub position: IVec3,
+    pub data: BlockEntityData,
+}
+
+pub fn block_entity_bundle(
+    storage: &VoxelWorld,
+    position: IVec3,
+    data: BlockEntityData,
+) -> impl Bundle {
+    let (_, [x, y, z]) = storage.split_pos(position);
+    (
+        BlockEntity { position },
+        data,
+        // Relative to the chunk.
+        Transform::from_xyz(x as f32, y as f32, z as f32),
+    )
+}
+
+fn spawn_block_entities(
+    mut commands: Commands,
+    mut spawn_events: EventReader<SpawnBlockEntity>,
+    // Loading a chunk replaces its block entities, so anything for one that's still loading has
+    // to wait until it's done.
+    mut deferred: Local<Vec<SpawnBlockEntity>>,
+    storage: Res<VoxelWorld>,
+    loaded: Res<LoadedChunks>,
+    query_children: Query<&Children>,
+    query_block_entities: Query<&BlockEntity>,
+    query_pending: Query<(), Or<(With<ChunkNeedsLoading>, With<ChunkNeedsGenerating>)>>,
+    mut query_chunks: Query<&mut Chunk>,
+) {
+    let events: Vec<_> = deferred
+        .drain(..)
+        .chain(spawn_events.read().cloned())
+        .collect();
+    for event in events {
+        let (chunk_pos, _) = storage.split_pos(event.position);
+        let Some(chunk_id) = loaded.get_entity(&chunk_pos) else {
+            continue;
+        };
+        if query_pending.contains(chunk_id) {
+            deferred.push(event);
+            continue;
+        }
+
+        let existing = query_children
+            .iter_descendants(chunk_id)
+            .filter(|&id| {
+                query_block_entities
+                    .get(id)
+                    .is_ok_and(|block_entity| block_entity.position == event.position)
+            })
+            .collect::<Vec<_>>();
+        for id in existing {
+            commands.entity(id).despawn_recursive();
+        }
+
+        commands.entity(chunk_id).with_children(|parent| {
+            parent.spawn(block_entity_bundle(&storage, event.position, event.data));
+        });
+        if let Ok(mut chunk) = query_chunks.get_mut(chunk_id) {
+            chunk.dirty = true;
+        }
+    }
+}
+
+/// Block entities only last as long as a block that wants one is still there.
+fn despawn_orphaned_block_entities(
+    mut commands: Commands,
+    storage: Res<VoxelWorld>,
+    registry: Res<Registry>,
+    mut removed_events: EventWriter<BlockEntityRemoved>,
+    query_block_entities: Query<(Entity, &BlockEntity, &BlockEntityData, &Parent)>,
+    mut query_chunks: Query<&mut Chunk>,
+) {
+    for (id, block_entity, data, parent) in &query_block_entities {
+        let wanted = storage
+            .get_block_at(block_entity.position)
+            .is_some_and(|block| registry.get_block(block).block_entity);
+        if wanted {
+            continue;
+        }
+
+        removed_events.send(BlockEntityRemoved {
+            position: block_entity.position,
+            data: data.clone(),
+        });
+        commands.entity(id).despawn_recursive();
+        if let Ok(mut chunk) = query_chunks.get_mut(parent.get()) {
+            chunk.dirty = true;
+        }
+    }
+}
+
+/// Flags chunks for saving when their block entities are modified. Ones that were just spawned
+/// were either loaded from the save or flagged their chunk already.
+fn mark_changed_block_entities(
+    query_block_entities: Query<(Ref<BlockEntityData>, &Parent)>,
+    mut query_chunks: Query<&mut Chunk>,
+) {
+    for (data, parent) in &query_block_entities {
+        if !data.is_changed() || data.is_added() {
+            continue;
+        }
+
+        if let Ok(mut chunk) = query_chunks.get_mut(parent.get()) {
+            chunk.dirty = true;
+        }
+    }
+}
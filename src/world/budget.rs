@@ -0,0 +1,157 @@
+use std::time::{Duration, Instant};
+
+use bevy::{
+    diagnostic::{DiagnosticPath, Diagnostics},
+    prelude::*,
+};
+
+use crate::{diagnostics::CHUNKS_PENDING_DIAGNOSTIC, render::ChunkNeedsMeshing, screens::Screen};
+
+use super::chunk::{Chunk, ChunkNeedsGenerating, ChunkNeedsLoading};
+
+pub struct ChunkBudgetPlugin;
+
+impl Plugin for ChunkBudgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChunkBudget>();
+        app.add_systems(OnEnter(Screen::Gameplay), reset_budget_phase);
+        app.add_systems(
+            Update,
+            update_budget_phase.run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+/// Which set of per-stage caps is currently in effect.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPhase {
+    /// Lots of chunks are waiting to be brought in, favour throughput.
+    #[default]
+    Loading,
+    /// Everything around the loaders is resident, favour a smooth frame time.
+    Steady,
+}
+
+/// How long a single chunk stage may run for each frame, in milliseconds.
+#[derive(Debug, Clone, Copy)]
+pub struct StageBudget {
+    pub loading_ms: f32,
+    pub steady_ms: f32,
+}
+
+impl StageBudget {
+    pub const fn new(loading_ms: f32, steady_ms: f32) -> Self {
+        Self {
+            loading_ms,
+            steady_ms,
+        }
+    }
+
+    pub fn limit(&self, phase: BudgetPhase) -> Duration {
+        let ms = match phase {
+            BudgetPhase::Loading => self.loading_ms,
+            BudgetPhase::Steady => self.steady_ms,
+        };
+        Duration::from_secs_f32(ms.max(0.) / 1000.)
+    }
+
+    /// Starts timing a stage for this frame.
+    pub fn start(&self, phase: BudgetPhase) -> BudgetTimer {
+        BudgetTimer {
+            start: Instant::now(),
+            limit: self.limit(phase),
+            chunks: 0,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct ChunkBudget {
+    pub phase: BudgetPhase,
+    /// Number of pending chunks above which we switch back to [`BudgetPhase::Loading`].
+    pub loading_threshold: usize,
+    pub generation: StageBudget,
+    pub loading: StageBudget,
+    pub saving: StageBudget,
+    pub meshing: StageBudget,
+}
+
+impl Default for ChunkBudget {
+    fn default() -> Self {
+        Self {
+            phase: BudgetPhase::Loading,
+            loading_threshold: 64,
+            generation: StageBudget::new(6., 2.),
+            loading: StageBudget::new(4., 1.5),
+            saving: StageBudget::new(2., 1.),
+            meshing: StageBudget::new(6., 2.),
+        }
+    }
+}
+
+/// Tracks how much of a stage's budget has been spent this frame.
+pub struct BudgetTimer {
+    start: Instant,
+    limit: Duration,
+    chunks: usize,
+}
+
+impl BudgetTimer {
+    /// At least one chunk is always let through so a tiny budget can't stall a stage entirely.
+    pub fn exhausted(&self) -> bool {
+        self.chunks > 0 && self.start.elapsed() >= self.limit
+    }
+
+    pub fn record_chunk(&mut self) {
+        self.chunks += 1;
+    }
+
+    /// Reports the frame's spend, skipping idle frames so they don't drag the averages down.
+    pub fn report(
+        &self,
+        diagnostics: &mut Diagnostics,
+        time_path: &DiagnosticPath,
+        count_path: &DiagnosticPath,
+    ) {
+        if self.chunks == 0 {
+            return;
+        }
+
+        let frame_time = self.start.elapsed().as_micros();
+        let chunks = self.chunks;
+        diagnostics.add_measurement(time_path, || frame_time as f64);
+        diagnostics.add_measurement(count_path, || chunks as f64);
+    }
+}
+
+fn reset_budget_phase(mut budget: ResMut<ChunkBudget>) {
+    budget.phase = BudgetPhase::Loading;
+}
+
+fn update_budget_phase(
+    mut diagnostics: Diagnostics,
+    mut budget: ResMut<ChunkBudget>,
+    query_chunks: Query<(), With<Chunk>>,
+    query_pending: Query<
+        (),
+        Or<(
+            With<ChunkNeedsLoading>,
+            With<ChunkNeedsGenerating>,
+            With<ChunkNeedsMeshing>,
+        )>,
+    >,
+) {
+    let pending = query_pending.iter().count();
+    diagnostics.add_measurement(&CHUNKS_PENDING_DIAGNOSTIC, || pending as f64);
+
+    // Nothing has been spawned yet, so we can't be done loading.
+    if query_chunks.is_empty() {
+        return;
+    }
+
+    budget.phase = match budget.phase {
+        BudgetPhase::Loading if pending == 0 => BudgetPhase::Steady,
+        BudgetPhase::Steady if pending > budget.loading_threshold => BudgetPhase::Loading,
+        phase => phase,
+    };
+}
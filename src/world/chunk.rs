@@ -8,14 +8,26 @@ use bevy::{diagnostic::Diagnostics, prelude::*};
 use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 
 use crate::{
-    assets::Registry,
-    diagnostics::{GEN_TIME_DIAGNOSTIC, LOAD_TIME_DIAGNOSTIC, SAVE_TIME_DIAGNOSTIC},
-    game::player::Player,
+    assets::{BlockType, Registry},
+    controls::{Action, ActionState},
+    diagnostics::{
+        GEN_COUNT_DIAGNOSTIC, GEN_FRAME_DIAGNOSTIC, GEN_TIME_DIAGNOSTIC, LOAD_COUNT_DIAGNOSTIC,
+        LOAD_FRAME_DIAGNOSTIC, LOAD_TIME_DIAGNOSTIC, SAVE_COUNT_DIAGNOSTIC, SAVE_FRAME_DIAGNOSTIC,
+        SAVE_TIME_DIAGNOSTIC,
+    },
     render::ChunkNeedsMeshing,
     screens::Screen,
 };
 
-use super::voxel_world::VoxelWorld;
+use super::{
+    block_entity::{block_entity_bundle, BlockEntity, BlockEntityData, SavedBlockEntities},
+    budget::ChunkBudget,
+    generation::{ProtoChunk, WorldGenerator},
+    heightmap::ChunkHeightmaps,
+    loader::{load_unload_chunks_around_loaders, LoadedChunks},
+    pending::{apply_blocks, PendingBlocks},
+    voxel_world::VoxelWorld,
+};
 
 pub struct ChunkPlugin;
 
@@ -24,7 +36,7 @@ impl Plugin for ChunkPlugin {
         app.add_systems(
             Update,
             (
-                load_unload_chunks_around_player,
+                load_unload_chunks_around_loaders,
                 (
                     generate_chunks,
                     sys_mark_save_all,
@@ -37,6 +49,10 @@ impl Plugin for ChunkPlugin {
                 .chain()
                 .run_if(in_state(Screen::Gameplay)),
         );
+        app.add_systems(
+            Last,
+            save_on_exit.run_if(in_state(Screen::Gameplay).and(on_event::<AppExit>)),
+        );
     }
 }
 
@@ -58,156 +74,58 @@ pub struct ChunkNeedsUnloading;
 #[derive(Component)]
 pub struct ChunkNeedsGenerating;
 
-fn load_unload_chunks_around_player(
-    mut commands: Commands,
-    storage: Res<VoxelWorld>,
-    query_player: Query<&Transform, With<Player>>,
-    query_chunks: Query<(Entity, &Chunk)>,
-) {
-    let player_translate = query_player.single().translation;
-    let player_chunk = [
-        (player_translate.x / storage.chunk_len as f32).floor() as i32,
-        (player_translate.y / storage.chunk_len as f32).floor() as i32,
-        (player_translate.z / storage.chunk_len as f32).floor() as i32,
-    ];
-
-    let chunk_radius = 4;
-    let load_region_side_length = chunk_radius * 2 + 1;
-    let num_chunks = usize::pow(load_region_side_length, 3);
-    let mut needs_spawning = vec![true; num_chunks];
-    for (id, chunk) in &query_chunks {
-        let mut exists = true;
-        for i in 0..3 {
-            if chunk.world_pos[i] < player_chunk[i] - chunk_radius as i32
-                || chunk.world_pos[i] > player_chunk[i] + chunk_radius as i32
-            {
-                exists = false;
-                break;
-            }
-        }
-
-        if exists {
-            let x = (chunk.world_pos[0] - (player_chunk[0] - chunk_radius as i32)) as usize;
-            let y = (chunk.world_pos[1] - (player_chunk[1] - chunk_radius as i32)) as usize;
-            let z = (chunk.world_pos[2] - (player_chunk[2] - chunk_radius as i32)) as usize;
-            let idx = x
-                + y * load_region_side_length
-                + z * load_region_side_length * load_region_side_length;
-            needs_spawning[idx] = false;
-        } else {
-            if chunk.dirty {
-                commands.entity(id).insert(ChunkNeedsSaving);
-            }
-            commands.entity(id).insert(ChunkNeedsUnloading);
-        }
-    }
-
-    let mut idx = 0;
-    for z in 0..load_region_side_length {
-        for y in 0..load_region_side_length {
-            for x in 0..load_region_side_length {
-                idx += 1;
-                if !needs_spawning[idx - 1] {
-                    continue;
-                }
-
-                let cx = player_chunk[0] - chunk_radius as i32 + x as i32;
-                let cy = player_chunk[1] - chunk_radius as i32 + y as i32;
-                let cz = player_chunk[2] - chunk_radius as i32 + z as i32;
-
-                commands.spawn((
-                    StateScoped(Screen::Gameplay),
-                    Chunk {
-                        world_pos: [cx, cy, cz],
-                        dirty: false,
-                    },
-                    ChunkNeedsLoading,
-                    Transform::from_xyz(cx as f32 * 32., cy as f32 * 32., cz as f32 * 32.),
-                ));
-            }
-        }
-    }
-}
-
 fn generate_chunks(
     mut commands: Commands,
     mut diagnostics: Diagnostics,
+    budget: Res<ChunkBudget>,
     mut storage: ResMut<VoxelWorld>,
     registry: Res<Registry>,
+    generator: Res<WorldGenerator>,
+    loaded: Res<LoadedChunks>,
+    mut pending: ResMut<PendingBlocks>,
     mut query_chunks: Query<(Entity, &mut Chunk), With<ChunkNeedsGenerating>>,
+    mut query_generated: Query<&mut Chunk, Without<ChunkNeedsGenerating>>,
 ) {
-    let voxels_per_chunk = storage.chunk_len * storage.chunk_len * storage.chunk_len;
-    let mut noise_vals = vec![0.0; voxels_per_chunk];
+    let air = registry.get_block_id("air");
 
-    let mut chunk_count = 0;
+    let mut budget_timer = budget.generation.start(budget.phase);
     for (id, mut chunk) in &mut query_chunks {
-        // TODO: Make chunks per frame configurable
-        if chunk_count > 8 {
-            return;
+        if budget_timer.exhausted() {
+            break;
         }
 
         let start_time = Instant::now();
 
-        let x = chunk.world_pos[0];
-        let y = chunk.world_pos[1];
-        let z = chunk.world_pos[2];
-
-        storage.terrain_noise.gen_uniform_grid_3d(
-            &mut noise_vals,
-            storage.chunk_len as i32 * x,
-            storage.chunk_len as i32 * y,
-            storage.chunk_len as i32 * z,
-            storage.chunk_len as i32,
-            storage.chunk_len as i32,
-            storage.chunk_len as i32,
-            storage.terrain_frequency,
+        let mut proto_chunk = ProtoChunk::new(
+            chunk.world_pos,
+            storage.chunk_len,
             storage.terrain_seed,
+            air,
         );
-
-        let mut chunk_voxels = vec![registry.get_block_id("air"); voxels_per_chunk];
-        (0..voxels_per_chunk).for_each(|i| {
-            if noise_vals[i] > 0. {
-                chunk_voxels[i] = registry.get_block_id("stone");
+        generator.0.generate(&mut proto_chunk);
+        pending.apply(&chunk.world_pos, &mut proto_chunk.voxels, air);
+        storage.load_chunk(&chunk.world_pos, proto_chunk.voxels);
+
+        for (target_pos, blocks) in proto_chunk.overflow {
+            let Some(target_voxels) = storage.get_chunk_mut(&target_pos) else {
+                pending.push(target_pos, blocks);
+                continue;
+            };
+
+            if !apply_blocks(target_voxels, &blocks, air) {
+                continue;
             }
-        });
+            storage.refresh_heightmaps(&target_pos);
 
-        for z in 0..storage.chunk_len {
-            for y in 0..storage.chunk_len {
-                for x in 0..storage.chunk_len {
-                    let i = storage.local_pos_to_idx(x, y, z);
-                    if chunk_voxels[i] == registry.get_block_id("air") {
-                        if y > 0
-                            && chunk_voxels[storage.local_pos_to_idx(x, y - 1, z)]
-                                != registry.get_block_id("air")
-                            && chunk_voxels[storage.local_pos_to_idx(x, y - 1, z)]
-                                != registry.get_block_id("stone_fence")
-                        {
-                            chunk_voxels[i] = registry.get_block_id("stone_fence");
-                        }
-
-                        continue;
-                    }
-
-                    for dy in 1..4 {
-                        if y + dy < storage.chunk_len
-                            && chunk_voxels[storage.local_pos_to_idx(x, y + dy, z)]
-                                == registry.get_block_id("air")
-                        {
-                            chunk_voxels[i] = if dy == 1 {
-                                registry.get_block_id("grass")
-                            } else {
-                                registry.get_block_id("dirt")
-                            };
-                            break;
-                        }
-                    }
+            if let Some(target_id) = loaded.get_entity(&target_pos) {
+                commands.entity(target_id).insert(ChunkNeedsMeshing);
+                // Chunks generated earlier this frame are still marked and already dirty.
+                if let Ok(mut target_chunk) = query_generated.get_mut(target_id) {
+                    target_chunk.dirty = true;
                 }
             }
         }
 
-        let world_pos = [x, y, z];
-        storage.load_chunk(&world_pos, chunk_voxels);
-
         chunk.dirty = true;
         commands
             .entity(id)
@@ -216,16 +134,22 @@ fn generate_chunks(
 
         let gen_time = (Instant::now() - start_time).as_micros();
         diagnostics.add_measurement(&GEN_TIME_DIAGNOSTIC, || gen_time as f64);
-        chunk_count += 1;
+        budget_timer.record_chunk();
     }
+
+    budget_timer.report(
+        &mut diagnostics,
+        &GEN_FRAME_DIAGNOSTIC,
+        &GEN_COUNT_DIAGNOSTIC,
+    );
 }
 
 fn sys_mark_save_all(
     mut commands: Commands,
-    keys: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     query_chunks: Query<Entity, (With<Chunk>, Without<ChunkNeedsSaving>)>,
 ) {
-    if keys.just_pressed(KeyCode::KeyO) {
+    if actions.just_pressed(Action::SaveAll) {
         for id in &query_chunks {
             commands.entity(id).insert(ChunkNeedsSaving);
         }
@@ -234,10 +158,10 @@ fn sys_mark_save_all(
 
 fn sys_mark_load_all(
     mut commands: Commands,
-    keys: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     query_chunks: Query<Entity, (With<Chunk>, Without<ChunkNeedsSaving>)>,
 ) {
-    if keys.just_pressed(KeyCode::KeyP) {
+    if actions.just_pressed(Action::LoadAll) {
         for id in &query_chunks {
             commands.entity(id).insert(ChunkNeedsLoading);
         }
@@ -247,63 +171,101 @@ fn sys_mark_load_all(
 fn sys_save_chunks(
     mut commands: Commands,
     mut diagnostics: Diagnostics,
+    budget: Res<ChunkBudget>,
     voxel_world: Res<VoxelWorld>,
-    mut query_chunks: Query<(Entity, &mut Chunk), With<ChunkNeedsSaving>>,
+    mut query_chunks: Query<(Entity, &mut Chunk, Option<&Children>), With<ChunkNeedsSaving>>,
+    query_block_entities: Query<(&BlockEntity, &BlockEntityData)>,
 ) {
-    let mut chunk_count = 0;
-    for (id, mut chunk) in &mut query_chunks {
-        // TODO: Make chunks per frame configurable
-        if chunk_count > 8 {
-            return;
+    let mut budget_timer = budget.saving.start(budget.phase);
+    for (id, mut chunk, children) in &mut query_chunks {
+        if budget_timer.exhausted() {
+            break;
         }
 
         let start_time = Instant::now();
 
-        let save_dir = format!("./saves/{}", voxel_world.world_name);
-        let _ = fs::create_dir(&save_dir);
-        let Ok(true) = fs::exists(&save_dir) else {
+        if !write_chunk(&voxel_world, &chunk, children, &query_block_entities) {
             continue;
-        };
-
-        let data = voxel_world.get_chunk(&chunk.world_pos).unwrap();
-        let buffer = bitcode::encode(data);
-
-        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
-        encoder.write_all(&buffer).unwrap();
-        let compressed_buffer = encoder.finish().unwrap();
-
-        let path = format!(
-            "{}/{}_{}_{}.dat",
-            &save_dir, chunk.world_pos[0], chunk.world_pos[1], chunk.world_pos[2]
-        );
-        let mut f = File::create(&path).unwrap();
-        f.write_all(&compressed_buffer).unwrap();
+        }
 
         chunk.dirty = false;
         commands.entity(id).remove::<ChunkNeedsSaving>();
 
         let save_time = (Instant::now() - start_time).as_micros();
         diagnostics.add_measurement(&SAVE_TIME_DIAGNOSTIC, || save_time as f64);
-        chunk_count += 1;
+        budget_timer.record_chunk();
     }
+
+    budget_timer.report(
+        &mut diagnostics,
+        &SAVE_FRAME_DIAGNOSTIC,
+        &SAVE_COUNT_DIAGNOSTIC,
+    );
+}
+
+/// Writes the chunk's voxels, heightmaps and block entities to its file in the save directory,
+/// returning whether it could.
+fn write_chunk(
+    voxel_world: &VoxelWorld,
+    chunk: &Chunk,
+    children: Option<&Children>,
+    query_block_entities: &Query<(&BlockEntity, &BlockEntityData)>,
+) -> bool {
+    let save_dir = voxel_world.save_dir();
+    let _ = fs::create_dir(&save_dir);
+    let Ok(true) = fs::exists(&save_dir) else {
+        return false;
+    };
+
+    let data = voxel_world.get_chunk(&chunk.world_pos).unwrap();
+    let heightmaps = voxel_world.get_heightmaps(&chunk.world_pos).unwrap();
+    let block_entities: SavedBlockEntities = children
+        .into_iter()
+        .flat_map(|children| children.iter())
+        .filter_map(|&child| query_block_entities.get(child).ok())
+        .map(|(block_entity, data)| {
+            let (_, local_pos) = voxel_world.split_pos(block_entity.position);
+            (local_pos, data.clone())
+        })
+        .collect();
+    // References encode just like what they point at, so nothing needs copying.
+    let buffer = bitcode::encode(&(data, heightmaps, &block_entities));
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    encoder.write_all(&buffer).unwrap();
+    let compressed_buffer = encoder.finish().unwrap();
+
+    let path = format!(
+        "{}/{}_{}_{}.dat",
+        &save_dir, chunk.world_pos[0], chunk.world_pos[1], chunk.world_pos[2]
+    );
+    let mut f = File::create(&path).unwrap();
+    f.write_all(&compressed_buffer).unwrap();
+
+    true
 }
 
 fn sys_load_chunks(
     mut commands: Commands,
     mut diagnostics: Diagnostics,
+    budget: Res<ChunkBudget>,
+    registry: Res<Registry>,
     mut voxel_world: ResMut<VoxelWorld>,
-    query_chunks: Query<(Entity, &Chunk), With<ChunkNeedsLoading>>,
+    mut pending: ResMut<PendingBlocks>,
+    mut query_chunks: Query<(Entity, &mut Chunk, Option<&Children>), With<ChunkNeedsLoading>>,
+    query_block_entities: Query<(), With<BlockEntity>>,
 ) {
-    let mut chunk_count = 0;
-    for (id, chunk) in &query_chunks {
-        // TODO: Make chunks per frame configurable
-        if chunk_count > 8 {
-            return;
+    let air = registry.get_block_id("air");
+
+    let mut budget_timer = budget.loading.start(budget.phase);
+    for (id, mut chunk, children) in &mut query_chunks {
+        if budget_timer.exhausted() {
+            break;
         }
 
         let start_time = Instant::now();
 
-        let save_dir = format!("./saves/{}", voxel_world.world_name);
+        let save_dir = voxel_world.save_dir();
         let path = format!(
             "{}/{}_{}_{}.dat",
             &save_dir, chunk.world_pos[0], chunk.world_pos[1], chunk.world_pos[2]
@@ -319,25 +281,66 @@ fn sys_load_chunks(
         let mut decoder = DeflateDecoder::new(f);
         decoder.read_to_end(&mut decompressed_buffer).unwrap();
 
-        let buffer = bitcode::decode(&decompressed_buffer).unwrap();
-        match voxel_world.get_chunk_mut(&chunk.world_pos) {
-            Some(data) => *data = buffer,
-            None => voxel_world.load_chunk(&chunk.world_pos, buffer),
+        // Older saves are missing the block entities, and older still the heightmaps too.
+        let (mut buffer, heightmaps, block_entities) = match bitcode::decode::<(
+            Vec<BlockType>,
+            ChunkHeightmaps,
+            SavedBlockEntities,
+        )>(&decompressed_buffer)
+        {
+            Ok((buffer, heightmaps, block_entities)) => (buffer, Some(heightmaps), block_entities),
+            Err(_) => {
+                match bitcode::decode::<(Vec<BlockType>, ChunkHeightmaps)>(&decompressed_buffer) {
+                    Ok((buffer, heightmaps)) => (buffer, Some(heightmaps), vec![]),
+                    Err(_) => (bitcode::decode(&decompressed_buffer).unwrap(), None, vec![]),
+                }
+            }
+        };
+        let applied_pending = pending.apply(&chunk.world_pos, &mut buffer, air);
+        match heightmaps {
+            Some(heightmaps) if !applied_pending => {
+                voxel_world.load_chunk_with_heightmaps(&chunk.world_pos, buffer, heightmaps)
+            }
+            _ => voxel_world.load_chunk(&chunk.world_pos, buffer),
+        }
+        if applied_pending {
+            chunk.dirty = true;
+        }
+
+        // Reloading replaces whatever block entities the chunk already had.
+        for &child in children.into_iter().flat_map(|children| children.iter()) {
+            if query_block_entities.contains(child) {
+                commands.entity(child).despawn_recursive();
+            }
         }
+        let origin = IVec3::from_array(chunk.world_pos) * voxel_world.chunk_len as i32;
+        commands.entity(id).with_children(|parent| {
+            for ([x, y, z], data) in block_entities {
+                let position = origin + IVec3::new(x as i32, y as i32, z as i32);
+                parent.spawn(block_entity_bundle(&voxel_world, position, data));
+            }
+        });
 
         commands.entity(id).remove::<ChunkNeedsLoading>();
         commands.entity(id).insert(ChunkNeedsMeshing);
 
         let load_time = (Instant::now() - start_time).as_micros();
         diagnostics.add_measurement(&LOAD_TIME_DIAGNOSTIC, || load_time as f64);
-        chunk_count += 1;
+        budget_timer.record_chunk();
     }
+
+    budget_timer.report(
+        &mut diagnostics,
+        &LOAD_FRAME_DIAGNOSTIC,
+        &LOAD_COUNT_DIAGNOSTIC,
+    );
 }
 
 fn scheduled_save(
     mut commands: Commands,
     time: Res<Time>,
     mut voxel_world: ResMut<VoxelWorld>,
+    mut pending: ResMut<PendingBlocks>,
     query_chunks: Query<(Entity, &Chunk), Without<ChunkNeedsSaving>>,
 ) {
     voxel_world.save_timer.tick(time.delta());
@@ -346,6 +349,7 @@ fn scheduled_save(
     }
 
     info!("saving...");
+    pending.save(&voxel_world.save_dir());
     for (id, chunk) in &query_chunks {
         if chunk.dirty {
             commands.entity(id).insert(ChunkNeedsSaving);
@@ -353,13 +357,46 @@ fn scheduled_save(
     }
 }
 
+/// Saves everything still waiting to be saved before the game closes, rather than leaving it to a
+/// save timer that won't get another chance to run.
+fn save_on_exit(
+    voxel_world: Res<VoxelWorld>,
+    mut pending: ResMut<PendingBlocks>,
+    // Chunks still waiting on their voxels have nothing to save yet.
+    mut query_chunks: Query<
+        (&mut Chunk, Option<&Children>, Has<ChunkNeedsSaving>),
+        (Without<ChunkNeedsLoading>, Without<ChunkNeedsGenerating>),
+    >,
+    query_block_entities: Query<(&BlockEntity, &BlockEntityData)>,
+) {
+    info!("saving before exit...");
+    pending.save(&voxel_world.save_dir());
+    for (mut chunk, children, needs_saving) in &mut query_chunks {
+        if (chunk.dirty || needs_saving)
+            && write_chunk(&voxel_world, &chunk, children, &query_block_entities)
+        {
+            chunk.dirty = false;
+        }
+    }
+}
+
 fn unload_chunks(
     mut commands: Commands,
     mut voxel_world: ResMut<VoxelWorld>,
-    query_chunks: Query<(Entity, &Chunk), With<ChunkNeedsUnloading>>,
+    mut loaded: ResMut<LoadedChunks>,
+    // Saving is budgeted, so a chunk may have to wait a few frames before it can go.
+    query_chunks: Query<(Entity, &Chunk), (With<ChunkNeedsUnloading>, Without<ChunkNeedsSaving>)>,
 ) {
     for (id, chunk) in &query_chunks {
+        // A loader may have picked the chunk back up before it got the chance to leave.
+        if loaded.is_requested(&chunk.world_pos) {
+            commands.entity(id).remove::<ChunkNeedsUnloading>();
+            continue;
+        }
+
         voxel_world.unload_chunk(&chunk.world_pos);
-        commands.entity(id).despawn();
+        loaded.forget_entity(&chunk.world_pos);
+        // Takes the chunk's block entities with it.
+        commands.entity(id).despawn_recursive();
     }
 }
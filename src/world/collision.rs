@@ -0,0 +1,130 @@
+use bevy::{
+    math::bounding::{Aabb3d, BoundingVolume, IntersectsVolume},
+    prelude::*,
+};
+
+use crate::assets::Registry;
+
+use super::VoxelWorld;
+
+/// Leeway for boxes that are exactly touching, so rounding doesn't let them sink into each other.
+const EPSILON: f32 = 1e-4;
+
+/// How every block collides.
+pub struct BlockCollision {
+    /// Collision boxes in block space. Blocks that don't block motion have none.
+    shapes: Vec<Vec<Aabb3d>>,
+    climbable: Vec<bool>,
+}
+
+impl BlockCollision {
+    pub fn from_registry(registry: &Registry) -> Self {
+        let blocks = (0..registry.block_count()).map(|id| registry.get_block(id));
+        Self {
+            shapes: blocks
+                .clone()
+                .map(|block| match block.model {
+                    Some(model) if block.motion_blocking => {
+                        registry.get_model(model).collision.clone()
+                    }
+                    _ => vec![],
+                })
+                .collect(),
+            climbable: blocks.map(|block| block.climbable).collect(),
+        }
+    }
+}
+
+impl VoxelWorld {
+    /// Collision boxes of every block overlapping `region`. Unloaded chunks count as solid, so
+    /// nothing falls out of the world before it has loaded.
+    pub fn collision_boxes(&self, region: Aabb3d) -> Vec<Aabb3d> {
+        let min = Vec3::from(region.min).floor().as_ivec3();
+        let max = Vec3::from(region.max).ceil().as_ivec3() - IVec3::ONE;
+
+        let full_block = [Aabb3d {
+            min: Vec3A::ZERO,
+            max: Vec3A::ONE,
+        }];
+
+        let mut boxes = vec![];
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let pos = IVec3::new(x, y, z);
+                    let shapes = match self.get_block_at(pos) {
+                        Some(block) => &self.block_collision.shapes[block][..],
+                        None => &full_block[..],
+                    };
+
+                    for shape in shapes {
+                        let shape = shape.translated_by(pos.as_vec3());
+                        if shape.intersects(&region) {
+                            boxes.push(shape);
+                        }
+                    }
+                }
+            }
+        }
+        boxes
+    }
+
+    /// Whether `aabb` overlaps anything solid, ignoring faces that are only touching.
+    pub fn overlaps(&self, aabb: Aabb3d) -> bool {
+        self.collision_boxes(aabb).iter().any(|b| {
+            (0..3).all(|a| aabb.max[a] > b.min[a] + EPSILON && aabb.min[a] < b.max[a] - EPSILON)
+        })
+    }
+
+    /// Whether `aabb` overlaps any climbable blocks.
+    pub fn overlaps_climbable(&self, aabb: Aabb3d) -> bool {
+        let min = Vec3::from(aabb.min + EPSILON).floor().as_ivec3();
+        let max = Vec3::from(aabb.max - EPSILON).floor().as_ivec3();
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let block = self.get_block_at(IVec3::new(x, y, z));
+                    if block.is_some_and(|block| self.block_collision.climbable[block]) {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// How far `aabb` can move along `motion` before running into solid blocks, resolved one axis
+    /// at a time starting with Y.
+    pub fn sweep(&self, aabb: Aabb3d, motion: Vec3) -> Vec3 {
+        let boxes = self.collision_boxes(aabb.merge(&aabb.translated_by(motion)));
+
+        let mut aabb = aabb;
+        let mut moved = Vec3::ZERO;
+        for axis in [1, 0, 2] {
+            moved[axis] = clip_axis(&aabb, &boxes, axis, motion[axis]);
+            let mut offset = Vec3::ZERO;
+            offset[axis] = moved[axis];
+            aabb.translate_by(offset);
+        }
+        moved
+    }
+}
+
+/// Shortens `delta` along `axis` so `aabb` stops at the first box in its way.
+fn clip_axis(aabb: &Aabb3d, boxes: &[Aabb3d], axis: usize, mut delta: f32) -> f32 {
+    for b in boxes {
+        let overlaps = (0..3)
+            .filter(|&a| a != axis)
+            .all(|a| aabb.max[a] > b.min[a] + EPSILON && aabb.min[a] < b.max[a] - EPSILON);
+        if !overlaps {
+            continue;
+        }
+
+        if delta > 0. && aabb.max[axis] <= b.min[axis] + EPSILON {
+            delta = delta.min(b.min[axis] - aabb.max[axis]);
+        } else if delta < 0. && aabb.min[axis] >= b.max[axis] - EPSILON {
+            delta = delta.max(b.max[axis] - aabb.min[axis]);
+        }
+    }
+    delta
+}
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use fastnoise2::SafeNode;
+
+use crate::assets::{Biome, NoiseDef};
+
+use super::{decorator::try_decorate, GenerationStage, ProtoChunk, StageKind};
+
+// Keeps the climate layers from lining up with each other or the terrain.
+const TEMPERATURE_SEED_OFFSET: i32 = 0x5eed_0001;
+const HUMIDITY_SEED_OFFSET: i32 = 0x5eed_0002;
+
+/// Blended biome influence on the density noise of a single column.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DensityModifier {
+    pub height: f32,
+    pub height_falloff: f32,
+    pub offset: f32,
+}
+
+impl DensityModifier {
+    pub fn apply(&self, density: f32, world_y: i32) -> f32 {
+        density + self.offset - (world_y as f32 - self.height) * self.height_falloff
+    }
+}
+
+struct ClimateNoise {
+    noise: SafeNode,
+    frequency: f32,
+    seed_offset: i32,
+}
+
+impl ClimateNoise {
+    fn new(def: &NoiseDef, seed_offset: i32) -> Self {
+        let noise = SafeNode::from_encoded_node_tree(&def.node_tree)
+            .unwrap_or_else(|_| panic!("invalid climate node tree `{}`", def.node_tree));
+        Self {
+            noise,
+            frequency: def.frequency,
+            seed_offset,
+        }
+    }
+
+    fn sample_columns(&self, chunk: &ProtoChunk) -> Vec<f32> {
+        let origin = chunk.origin();
+        let len = chunk.chunk_len as i32;
+        let mut vals = vec![0.0; chunk.columns.len()];
+        self.noise.gen_uniform_grid_2d(
+            &mut vals,
+            origin[0],
+            origin[2],
+            len,
+            len,
+            self.frequency,
+            chunk.seed.wrapping_add(self.seed_offset),
+        );
+        vals
+    }
+}
+
+/// Assigns every column its biome and blended density modifier.
+pub struct ClimateStage {
+    temperature: ClimateNoise,
+    humidity: ClimateNoise,
+    blend: f32,
+    biomes: Vec<Arc<Biome>>,
+}
+
+impl ClimateStage {
+    pub fn new(
+        temperature: &NoiseDef,
+        humidity: &NoiseDef,
+        blend: f32,
+        biomes: Vec<Arc<Biome>>,
+    ) -> Self {
+        assert!(!biomes.is_empty(), "a biome stage needs at least one biome");
+        Self {
+            temperature: ClimateNoise::new(temperature, TEMPERATURE_SEED_OFFSET),
+            humidity: ClimateNoise::new(humidity, HUMIDITY_SEED_OFFSET),
+            blend,
+            biomes,
+        }
+    }
+
+    fn weight(&self, distance: f32, nearest: f32) -> f32 {
+        if self.blend <= 0. {
+            return if distance <= nearest { 1. } else { 0. };
+        }
+
+        let t = (1. - (distance - nearest) / self.blend).max(0.);
+        t * t
+    }
+}
+
+impl GenerationStage for ClimateStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Climate
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let temperature = self.temperature.sample_columns(chunk);
+        let humidity = self.humidity.sample_columns(chunk);
+
+        for i in 0..chunk.columns.len() {
+            let climate = Vec2::new(temperature[i], humidity[i]);
+
+            let mut nearest = 0;
+            let mut nearest_distance = f32::MAX;
+            for (b, biome) in self.biomes.iter().enumerate() {
+                let distance = biome.climate.distance(climate);
+                if distance < nearest_distance {
+                    nearest = b;
+                    nearest_distance = distance;
+                }
+            }
+
+            // Weighting by how much further away each biome is than the nearest one keeps the
+            // blend continuous across borders, where two biomes are equally near.
+            let mut density = DensityModifier::default();
+            let mut total_weight = 0.;
+            for biome in &self.biomes {
+                let w = self.weight(biome.climate.distance(climate), nearest_distance);
+                density.height += biome.height * w;
+                density.height_falloff += biome.height_falloff * w;
+                density.offset += biome.density_offset * w;
+                total_weight += w;
+            }
+            density.height /= total_weight;
+            density.height_falloff /= total_weight;
+            density.offset /= total_weight;
+
+            let column = &mut chunk.columns[i];
+            column.biome = Some(self.biomes[nearest].clone());
+            column.density = density;
+        }
+    }
+}
+
+/// Places each column's biome decorations.
+pub struct BiomeDecorationStage;
+
+impl GenerationStage for BiomeDecorationStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Decorator
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let len = chunk.chunk_len;
+        for z in 0..len {
+            for x in 0..len {
+                let Some(biome) = chunk.columns[chunk.column_idx(x, z)].biome.clone() else {
+                    continue;
+                };
+
+                for y in 0..len {
+                    for decoration in &biome.decorations {
+                        if try_decorate(chunk, decoration, x, y, z) {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
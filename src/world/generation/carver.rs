@@ -0,0 +1,253 @@
+use std::{
+    f32::consts::{PI, TAU},
+    sync::{Arc, Mutex},
+};
+
+use bevy::{prelude::*, utils::HashMap};
+use fastnoise2::SafeNode;
+
+use super::{rng::Rng, GenerationStage, ProtoChunk, StageKind};
+
+const CHEESE_SEED_OFFSET: i32 = 0x5eed_0003;
+const WORM_SALT: u32 = 0xca7e_0000;
+
+/// Hollows out large open caves wherever the noise is above `threshold`.
+pub struct CheeseCaveCarver {
+    noise: SafeNode,
+    frequency: f32,
+    threshold: f32,
+    max_height: i32,
+}
+
+impl CheeseCaveCarver {
+    pub fn new(encoded_node_tree: &str, frequency: f32, threshold: f32, max_height: i32) -> Self {
+        let noise = SafeNode::from_encoded_node_tree(encoded_node_tree)
+            .unwrap_or_else(|_| panic!("invalid cave node tree `{encoded_node_tree}`"));
+        Self {
+            noise,
+            frequency,
+            threshold,
+            max_height,
+        }
+    }
+}
+
+impl GenerationStage for CheeseCaveCarver {
+    fn kind(&self) -> StageKind {
+        StageKind::Carver
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let origin = chunk.origin();
+        if origin[1] >= self.max_height {
+            return;
+        }
+
+        let len = chunk.chunk_len as i32;
+        let mut noise_vals = vec![0.0; chunk.voxels.len()];
+        self.noise.gen_uniform_grid_3d(
+            &mut noise_vals,
+            origin[0],
+            origin[1],
+            origin[2],
+            len,
+            len,
+            len,
+            self.frequency,
+            chunk.seed.wrapping_add(CHEESE_SEED_OFFSET),
+        );
+
+        for z in 0..chunk.chunk_len {
+            for y in 0..chunk.chunk_len {
+                if origin[1] + y as i32 >= self.max_height {
+                    break;
+                }
+
+                for x in 0..chunk.chunk_len {
+                    let i = chunk.local_pos_to_idx(x, y, z);
+                    if noise_vals[i] > self.threshold {
+                        chunk.voxels[i] = chunk.air;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Winding tunnels, or ravines when `vertical_scale` is large and `max_pitch` small.
+///
+/// A worm is seeded from the chunk it starts in, and every chunk it could reach replays it from
+/// the start, carving only its own voxels. That keeps them seamless without having to hand
+/// anything between chunks. Paths are cached by start chunk, since neighbouring chunks keep asking
+/// for the same ones.
+pub struct WormCarver {
+    settings: WormSettings,
+    cache: Mutex<WormCache>,
+}
+
+pub struct WormSettings {
+    /// Keeps worm stages in the same generator from digging identical worms.
+    pub salt: u32,
+    /// Chance of a chunk starting any worms at all.
+    pub chance: f32,
+    pub max_count: u32,
+    pub min_length: u32,
+    pub max_length: u32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    pub vertical_scale: f32,
+    pub max_pitch: f32,
+}
+
+/// Start chunks whose worms are remembered. Most chunks don't start any, so are cheap to keep.
+const WORM_CACHE_SIZE: usize = 4096;
+
+/// Every ellipsoid the worms from one start chunk carve, as centre, radius and vertical radius.
+type WormPath = Vec<(Vec3, f32, f32)>;
+
+/// Recently used worm paths, keyed by seed and start chunk.
+#[derive(Default)]
+struct WormCache {
+    paths: HashMap<(i32, [i32; 3]), (Arc<WormPath>, u64)>,
+    tick: u64,
+}
+
+impl WormCache {
+    fn get(&mut self, key: (i32, [i32; 3])) -> Option<Arc<WormPath>> {
+        self.tick += 1;
+        let (path, last_used) = self.paths.get_mut(&key)?;
+        *last_used = self.tick;
+        Some(path.clone())
+    }
+
+    fn insert(&mut self, key: (i32, [i32; 3]), path: Arc<WormPath>) {
+        // Dropping the least recently used half at once keeps eviction cheap on average.
+        if self.paths.len() >= WORM_CACHE_SIZE {
+            let mut ticks: Vec<_> = self.paths.values().map(|(_, tick)| *tick).collect();
+            let mid = ticks.len() / 2;
+            let cutoff = *ticks.select_nth_unstable(mid).1;
+            self.paths.retain(|_, (_, tick)| *tick > cutoff);
+        }
+        self.tick += 1;
+        self.paths.insert(key, (path, self.tick));
+    }
+}
+
+impl WormCarver {
+    pub fn new(settings: WormSettings) -> Self {
+        Self {
+            settings,
+            cache: Mutex::default(),
+        }
+    }
+
+    fn reach(&self, chunk_len: usize) -> i32 {
+        let settings = &self.settings;
+        let reach =
+            settings.max_length as f32 + settings.max_radius * settings.vertical_scale.max(1.);
+        (reach / chunk_len as f32).ceil() as i32
+    }
+
+    fn worm_path(&self, seed: i32, chunk_len: usize, start_chunk: [i32; 3]) -> Arc<WormPath> {
+        let key = (seed, start_chunk);
+        if let Some(path) = self.cache.lock().unwrap().get(key) {
+            return path;
+        }
+
+        // Worked out without holding the lock, so other chunks can generate meanwhile.
+        let path = Arc::new(self.trace_worms(seed, chunk_len, start_chunk));
+        self.cache.lock().unwrap().insert(key, path.clone());
+        path
+    }
+
+    fn trace_worms(&self, seed: i32, chunk_len: usize, start_chunk: [i32; 3]) -> WormPath {
+        let settings = &self.settings;
+        let mut rng = Rng::new(seed, start_chunk, WORM_SALT ^ settings.salt);
+        if rng.next_f32() >= settings.chance {
+            return vec![];
+        }
+
+        let len = chunk_len as f32;
+        let start_origin = Vec3::new(
+            start_chunk[0] as f32 * len,
+            start_chunk[1] as f32 * len,
+            start_chunk[2] as f32 * len,
+        );
+
+        let mut path = vec![];
+        let count = rng.range_u32(1, settings.max_count.max(1));
+        for _ in 0..count {
+            let mut pos =
+                start_origin + Vec3::new(rng.next_f32(), rng.next_f32(), rng.next_f32()) * len;
+            let mut yaw = rng.next_f32() * TAU;
+            let mut pitch = (rng.next_f32() * 2. - 1.) * settings.max_pitch;
+            let length = rng.range_u32(settings.min_length, settings.max_length);
+            let radius = rng.range_f32(settings.min_radius, settings.max_radius);
+
+            for step in 0..length {
+                // Taper both ends so tunnels don't start or stop as flat walls.
+                let t = step as f32 / length as f32;
+                let r = radius * (0.4 + 0.6 * (t * PI).sin());
+                path.push((pos, r, r * settings.vertical_scale));
+
+                pos += Vec3::new(
+                    pitch.cos() * yaw.cos(),
+                    pitch.sin(),
+                    pitch.cos() * yaw.sin(),
+                );
+                yaw += (rng.next_f32() - 0.5) * 0.5;
+                pitch = (pitch + (rng.next_f32() - 0.5) * 0.3)
+                    .clamp(-settings.max_pitch, settings.max_pitch);
+            }
+        }
+        path
+    }
+}
+
+impl GenerationStage for WormCarver {
+    fn kind(&self) -> StageKind {
+        StageKind::Carver
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let reach = self.reach(chunk.chunk_len);
+        let pos = chunk.chunk_pos;
+        for z in -reach..=reach {
+            for y in -reach..=reach {
+                for x in -reach..=reach {
+                    let start_chunk = [pos[0] + x, pos[1] + y, pos[2] + z];
+                    let path = self.worm_path(chunk.seed, chunk.chunk_len, start_chunk);
+                    for &(center, radius, vertical_radius) in path.iter() {
+                        carve_ellipsoid(chunk, center, radius, vertical_radius);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Clears the voxels of `chunk` that fall within the ellipsoid.
+fn carve_ellipsoid(chunk: &mut ProtoChunk, center: Vec3, radius: f32, vertical_radius: f32) {
+    let origin = chunk.origin();
+    let origin = Vec3::new(origin[0] as f32, origin[1] as f32, origin[2] as f32);
+    let local = center - origin;
+    let extents = Vec3::new(radius, vertical_radius, radius);
+
+    let len = chunk.chunk_len as f32;
+    let min = (local - extents).floor().max(Vec3::ZERO);
+    let max = (local + extents).ceil().min(Vec3::splat(len - 1.));
+    if min.x > max.x || min.y > max.y || min.z > max.z {
+        return;
+    }
+
+    for z in min.z as usize..=max.z as usize {
+        for y in min.y as usize..=max.y as usize {
+            for x in min.x as usize..=max.x as usize {
+                let offset = (Vec3::new(x as f32, y as f32, z as f32) + 0.5 - local) / extents;
+                if offset.length_squared() <= 1. {
+                    chunk.set(x, y, z, chunk.air);
+                }
+            }
+        }
+    }
+}
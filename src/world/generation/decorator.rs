@@ -0,0 +1,53 @@
+use crate::assets::Decoration;
+
+use super::{rng::Rng, GenerationStage, ProtoChunk, StageKind};
+
+/// Scatters a decoration over the whole chunk, regardless of biome.
+pub struct DecoratorStage {
+    pub decoration: Decoration,
+}
+
+impl GenerationStage for DecoratorStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Decorator
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let len = chunk.chunk_len;
+        for z in 0..len {
+            for y in 0..len {
+                for x in 0..len {
+                    try_decorate(chunk, &self.decoration, x, y, z);
+                }
+            }
+        }
+    }
+}
+
+/// Places the decoration on top of the block at `(x, y, z)` if it suits it, there's air above and
+/// the roll succeeds. Decorations on the top layer spill over into the chunk above.
+pub(super) fn try_decorate(
+    chunk: &mut ProtoChunk,
+    decoration: &Decoration,
+    x: usize,
+    y: usize,
+    z: usize,
+) -> bool {
+    if !decoration.on.contains(&chunk.get(x, y, z)) || chunk.get_extended(x, y + 1, z) != chunk.air
+    {
+        return false;
+    }
+
+    let origin = chunk.origin();
+    let world_pos = [
+        origin[0] + x as i32,
+        origin[1] + y as i32 + 1,
+        origin[2] + z as i32,
+    ];
+    if Rng::new(chunk.seed, world_pos, decoration.block as u32).next_f32() >= decoration.chance {
+        return false;
+    }
+
+    chunk.place(world_pos, decoration.block);
+    true
+}
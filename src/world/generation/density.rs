@@ -0,0 +1,63 @@
+use fastnoise2::SafeNode;
+
+use crate::assets::BlockType;
+
+use super::{GenerationStage, ProtoChunk, StageKind, APRON};
+
+/// Fills the chunk with `block` wherever the noise is above `threshold`.
+pub struct DensityStage {
+    noise: SafeNode,
+    frequency: f32,
+    threshold: f32,
+    block: BlockType,
+}
+
+impl DensityStage {
+    pub fn new(encoded_node_tree: &str, frequency: f32, threshold: f32, block: BlockType) -> Self {
+        let noise = SafeNode::from_encoded_node_tree(encoded_node_tree)
+            .unwrap_or_else(|_| panic!("invalid density node tree `{encoded_node_tree}`"));
+        Self {
+            noise,
+            frequency,
+            threshold,
+            block,
+        }
+    }
+}
+
+impl GenerationStage for DensityStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Density
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let origin = chunk.origin();
+        let len = chunk.chunk_len;
+        let height = len + APRON;
+        let mut noise_vals = vec![0.0; len * height * len];
+        self.noise.gen_uniform_grid_3d(
+            &mut noise_vals,
+            origin[0],
+            origin[1],
+            origin[2],
+            len as i32,
+            height as i32,
+            len as i32,
+            self.frequency,
+            chunk.seed,
+        );
+
+        for z in 0..len {
+            for y in 0..height {
+                let world_y = origin[1] + y as i32;
+                for x in 0..len {
+                    let density = noise_vals[x + y * len + z * len * height];
+                    let modifier = &chunk.columns[chunk.column_idx(x, z)].density;
+                    if modifier.apply(density, world_y) > self.threshold {
+                        chunk.set_extended(x, y, z, self.block);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::assets::BlockType;
+
+use super::{GenerationStage, ProtoChunk, StageKind, APRON};
+
+/// Stacks `layers` of blocks upwards from `bottom`, the same in every column.
+pub struct LayersStage {
+    pub bottom: i32,
+    pub layers: Vec<(BlockType, u32)>,
+}
+
+impl LayersStage {
+    fn block_at(&self, world_y: i32) -> Option<BlockType> {
+        let mut top = self.bottom;
+        for &(block, thickness) in &self.layers {
+            top += thickness as i32;
+            if world_y < top {
+                return (world_y >= self.bottom).then_some(block);
+            }
+        }
+        None
+    }
+}
+
+impl GenerationStage for LayersStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Density
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let origin = chunk.origin();
+        let len = chunk.chunk_len;
+        for y in 0..len + APRON {
+            let Some(block) = self.block_at(origin[1] + y as i32) else {
+                continue;
+            };
+
+            for z in 0..len {
+                for x in 0..len {
+                    chunk.set_extended(x, y, z, block);
+                }
+            }
+        }
+    }
+}
+
+/// Lays out one of every block in a square grid at `height`, `spacing` blocks apart.
+pub struct BlockGridStage {
+    pub height: i32,
+    pub spacing: u32,
+    pub blocks: Vec<BlockType>,
+}
+
+impl BlockGridStage {
+    fn block_at(&self, world_x: i32, world_z: i32) -> Option<BlockType> {
+        let spacing = self.spacing.max(1) as i32;
+        if world_x < 0 || world_z < 0 || world_x % spacing != 0 || world_z % spacing != 0 {
+            return None;
+        }
+
+        let side = (self.blocks.len() as f32).sqrt().ceil() as i32;
+        let (col, row) = (world_x / spacing, world_z / spacing);
+        if col >= side {
+            return None;
+        }
+        self.blocks.get((col + row * side) as usize).copied()
+    }
+}
+
+impl GenerationStage for BlockGridStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Structure
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let origin = chunk.origin();
+        let len = chunk.chunk_len as i32;
+        let y = self.height - origin[1];
+        if !(0..len).contains(&y) {
+            return;
+        }
+
+        for z in 0..len {
+            for x in 0..len {
+                if let Some(block) = self.block_at(origin[0] + x, origin[2] + z) {
+                    chunk.set(x as usize, y as usize, z as usize, block);
+                }
+            }
+        }
+    }
+}
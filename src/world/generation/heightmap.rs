@@ -0,0 +1,120 @@
+use fastnoise2::SafeNode;
+
+use crate::assets::{BlockType, NoiseDef};
+
+use super::{GenerationStage, ProtoChunk, StageKind, APRON};
+
+/// Fills every column with `block` up to a height taken from 2D noise, so there are no overhangs or
+/// floating terrain. Biome density modifiers don't apply here, biomes only pick surface blocks.
+pub struct HeightmapStage {
+    noise: SafeNode,
+    frequency: f32,
+    octaves: u32,
+    lacunarity: f32,
+    persistence: f32,
+    /// Sorted `(noise, height)` points the summed noise is mapped through.
+    curve: Vec<(f32, f32)>,
+    block: BlockType,
+}
+
+impl HeightmapStage {
+    pub fn new(
+        height: &NoiseDef,
+        octaves: u32,
+        lacunarity: f32,
+        persistence: f32,
+        mut curve: Vec<(f32, f32)>,
+        block: BlockType,
+    ) -> Self {
+        assert!(
+            !curve.is_empty(),
+            "a heightmap stage needs at least one curve point"
+        );
+        let noise = SafeNode::from_encoded_node_tree(&height.node_tree)
+            .unwrap_or_else(|_| panic!("invalid heightmap node tree `{}`", height.node_tree));
+        curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            noise,
+            frequency: height.frequency,
+            octaves: octaves.max(1),
+            lacunarity,
+            persistence,
+            curve,
+            block,
+        }
+    }
+
+    /// Noise summed over all octaves, normalised back to roughly `[-1, 1]`.
+    fn sample_columns(&self, chunk: &ProtoChunk) -> Vec<f32> {
+        let origin = chunk.origin();
+        let len = chunk.chunk_len as i32;
+        let mut total = vec![0.0; chunk.columns.len()];
+        let mut octave = vec![0.0; chunk.columns.len()];
+
+        let mut frequency = self.frequency;
+        let mut amplitude = 1.;
+        let mut total_amplitude = 0.;
+        for o in 0..self.octaves {
+            self.noise.gen_uniform_grid_2d(
+                &mut octave,
+                origin[0],
+                origin[2],
+                len,
+                len,
+                frequency,
+                chunk.seed.wrapping_add(o as i32),
+            );
+            for (t, v) in total.iter_mut().zip(&octave) {
+                *t += v * amplitude;
+            }
+
+            total_amplitude += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+        }
+
+        for t in &mut total {
+            *t /= total_amplitude;
+        }
+        total
+    }
+
+    /// Piecewise linear lookup into [`Self::curve`], clamped at either end.
+    fn height(&self, noise: f32) -> i32 {
+        let i = self.curve.partition_point(|p| p.0 < noise);
+        let height = if i == 0 {
+            self.curve[0].1
+        } else if i == self.curve.len() {
+            self.curve[i - 1].1
+        } else {
+            let (x0, y0) = self.curve[i - 1];
+            let (x1, y1) = self.curve[i];
+            y0 + (y1 - y0) * (noise - x0) / (x1 - x0)
+        };
+        height.floor() as i32
+    }
+}
+
+impl GenerationStage for HeightmapStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Density
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let origin = chunk.origin();
+        let len = chunk.chunk_len;
+        let noise_vals = self.sample_columns(chunk);
+
+        for z in 0..len {
+            for x in 0..len {
+                let column = chunk.column_idx(x, z);
+                let height = self.height(noise_vals[column]);
+
+                let top = (height - origin[1] + 1).clamp(0, (len + APRON) as i32) as usize;
+                for y in 0..top {
+                    chunk.set_extended(x, y, z, self.block);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Chunk generation, assembled from the stages listed in a `*.worldgen.ron` asset.
+
+mod biome;
+mod carver;
+mod decorator;
+mod density;
+mod flat;
+mod heightmap;
+mod ore;
+mod rng;
+mod structure;
+mod surface;
+
+use std::sync::Arc;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::assets::{Biome, BlockType, Decoration, Registry, StageDef, WorldGenDef};
+
+pub use biome::DensityModifier;
+
+use self::{
+    biome::{BiomeDecorationStage, ClimateStage},
+    carver::{CheeseCaveCarver, WormCarver, WormSettings},
+    decorator::DecoratorStage,
+    density::DensityStage,
+    flat::{BlockGridStage, LayersStage},
+    heightmap::HeightmapStage,
+    ore::OreStage,
+    rng::name_salt,
+    structure::{Placement, StructureStage},
+    surface::SurfaceStage,
+};
+
+/// Rows of density kept above the chunk, so surface rules can tell whether the top of the chunk is
+/// actually exposed.
+pub const APRON: usize = 8;
+
+/// Produces the voxels for a single chunk.
+pub trait TerrainGenerator: Send + Sync {
+    fn generate(&self, chunk: &mut ProtoChunk);
+}
+
+/// Where a stage runs within a [`StagedGenerator`]. Stages run in this order, and in the order they
+/// were added within the same kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StageKind {
+    Climate,
+    Density,
+    Surface,
+    Carver,
+    Ore,
+    Structure,
+    Decorator,
+}
+
+/// A single pluggable step of a [`StagedGenerator`].
+pub trait GenerationStage: Send + Sync {
+    fn kind(&self) -> StageKind;
+
+    fn apply(&self, chunk: &mut ProtoChunk);
+}
+
+/// A chunk that is still being generated.
+pub struct ProtoChunk {
+    pub chunk_pos: [i32; 3],
+    pub chunk_len: usize,
+    pub seed: i32,
+    pub air: BlockType,
+    pub voxels: Vec<BlockType>,
+    /// The [`APRON`] rows of terrain directly above the chunk, as laid down by the density stages.
+    pub above: Vec<BlockType>,
+    pub columns: Vec<Column>,
+    /// Blocks placed outside of this chunk, by chunk position and voxel index.
+    pub overflow: HashMap<[i32; 3], Vec<(usize, BlockType)>>,
+}
+
+/// Per column data shared between stages.
+#[derive(Default, Clone)]
+pub struct Column {
+    pub biome: Option<Arc<Biome>>,
+    pub density: DensityModifier,
+}
+
+impl ProtoChunk {
+    pub fn new(chunk_pos: [i32; 3], chunk_len: usize, seed: i32, air: BlockType) -> Self {
+        Self {
+            chunk_pos,
+            chunk_len,
+            seed,
+            air,
+            voxels: vec![air; chunk_len * chunk_len * chunk_len],
+            above: vec![air; chunk_len * APRON * chunk_len],
+            columns: vec![Column::default(); chunk_len * chunk_len],
+            overflow: HashMap::new(),
+        }
+    }
+
+    /// World space position of the chunk's (0, 0, 0) voxel.
+    pub fn origin(&self) -> [i32; 3] {
+        let len = self.chunk_len as i32;
+        [
+            self.chunk_pos[0] * len,
+            self.chunk_pos[1] * len,
+            self.chunk_pos[2] * len,
+        ]
+    }
+
+    pub fn local_pos_to_idx(&self, x: usize, y: usize, z: usize) -> usize {
+        x + y * self.chunk_len + z * self.chunk_len * self.chunk_len
+    }
+
+    pub fn column_idx(&self, x: usize, z: usize) -> usize {
+        x + z * self.chunk_len
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> BlockType {
+        self.voxels[self.local_pos_to_idx(x, y, z)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: BlockType) {
+        let idx = self.local_pos_to_idx(x, y, z);
+        self.voxels[idx] = block;
+    }
+
+    fn apron_idx(&self, x: usize, y: usize, z: usize) -> usize {
+        x + (y - self.chunk_len) * self.chunk_len + z * self.chunk_len * APRON
+    }
+
+    /// Like [`Self::get`], but `y` may reach up into the [`APRON`].
+    pub fn get_extended(&self, x: usize, y: usize, z: usize) -> BlockType {
+        if y < self.chunk_len {
+            self.get(x, y, z)
+        } else {
+            self.above[self.apron_idx(x, y, z)]
+        }
+    }
+
+    /// Like [`Self::set`], but `y` may reach up into the [`APRON`].
+    pub fn set_extended(&mut self, x: usize, y: usize, z: usize, block: BlockType) {
+        if y < self.chunk_len {
+            self.set(x, y, z, block);
+        } else {
+            let idx = self.apron_idx(x, y, z);
+            self.above[idx] = block;
+        }
+    }
+
+    /// Places `block` at a world position if it's currently air. Positions outside of the chunk
+    /// end up in [`Self::overflow`] to be applied once their chunk exists.
+    pub fn place(&mut self, world_pos: [i32; 3], block: BlockType) {
+        let len = self.chunk_len as i32;
+        let chunk_pos = [
+            world_pos[0].div_euclid(len),
+            world_pos[1].div_euclid(len),
+            world_pos[2].div_euclid(len),
+        ];
+        let idx = self.local_pos_to_idx(
+            world_pos[0].rem_euclid(len) as usize,
+            world_pos[1].rem_euclid(len) as usize,
+            world_pos[2].rem_euclid(len) as usize,
+        );
+
+        if chunk_pos != self.chunk_pos {
+            self.overflow
+                .entry(chunk_pos)
+                .or_default()
+                .push((idx, block));
+        } else if self.voxels[idx] == self.air {
+            self.voxels[idx] = block;
+        }
+    }
+}
+
+/// Runs its stages one after another over each chunk.
+#[derive(Default)]
+pub struct StagedGenerator {
+    stages: Vec<Box<dyn GenerationStage>>,
+}
+
+impl StagedGenerator {
+    pub fn from_def(def: &WorldGenDef, registry: &Registry) -> Self {
+        let mut generator = Self::default();
+        for stage in &def.stages {
+            add_stages(&mut generator, stage, registry);
+        }
+        generator
+    }
+
+    pub fn add_stage(&mut self, stage: Box<dyn GenerationStage>) {
+        // Stable, so stages of the same kind keep the order they were added in.
+        let idx = self.stages.partition_point(|s| s.kind() <= stage.kind());
+        self.stages.insert(idx, stage);
+    }
+}
+
+impl TerrainGenerator for StagedGenerator {
+    fn generate(&self, chunk: &mut ProtoChunk) {
+        for stage in &self.stages {
+            stage.apply(chunk);
+        }
+    }
+}
+
+/// The generator used for the currently loaded world.
+#[derive(Resource)]
+pub struct WorldGenerator(pub Box<dyn TerrainGenerator>);
+
+impl WorldGenerator {
+    pub fn from_def(def: &WorldGenDef, registry: &Registry) -> Self {
+        Self(Box::new(StagedGenerator::from_def(def, registry)))
+    }
+}
+
+fn add_stages(generator: &mut StagedGenerator, def: &StageDef, registry: &Registry) {
+    match def {
+        StageDef::Density {
+            node_tree,
+            frequency,
+            threshold,
+            block,
+        } => generator.add_stage(Box::new(DensityStage::new(
+            node_tree,
+            *frequency,
+            *threshold,
+            block_id(registry, block),
+        ))),
+        StageDef::Heightmap {
+            height,
+            octaves,
+            lacunarity,
+            persistence,
+            curve,
+            block,
+        } => generator.add_stage(Box::new(HeightmapStage::new(
+            height,
+            *octaves,
+            *lacunarity,
+            *persistence,
+            curve.clone(),
+            block_id(registry, block),
+        ))),
+        StageDef::Layers { bottom, layers } => generator.add_stage(Box::new(LayersStage {
+            bottom: *bottom,
+            layers: layers
+                .iter()
+                .map(|(block, thickness)| (block_id(registry, block), *thickness))
+                .collect(),
+        })),
+        StageDef::BlockGrid { height, spacing } => {
+            let air = block_id(registry, "air");
+            generator.add_stage(Box::new(BlockGridStage {
+                height: *height,
+                spacing: *spacing,
+                blocks: (0..registry.block_count()).filter(|&b| b != air).collect(),
+            }))
+        }
+        StageDef::Surface {
+            top,
+            filler,
+            depth,
+            replaceable,
+        } => generator.add_stage(Box::new(SurfaceStage {
+            top: block_id(registry, top),
+            filler: block_id(registry, filler),
+            depth: *depth,
+            replaceable: block_ids(registry, replaceable),
+        })),
+        StageDef::Biomes {
+            temperature,
+            humidity,
+            blend,
+            biomes,
+        } => {
+            let biomes = biomes
+                .iter()
+                .map(|name| {
+                    let id = registry
+                        .find_biome_id(name)
+                        .unwrap_or_else(|| panic!("worldgen references unknown biome `{name}`"));
+                    Arc::new(registry.get_biome(id).clone())
+                })
+                .collect();
+            generator.add_stage(Box::new(ClimateStage::new(
+                temperature,
+                humidity,
+                *blend,
+                biomes,
+            )));
+            generator.add_stage(Box::new(BiomeDecorationStage));
+        }
+        StageDef::Caves {
+            node_tree,
+            frequency,
+            threshold,
+            max_height,
+        } => generator.add_stage(Box::new(CheeseCaveCarver::new(
+            node_tree,
+            *frequency,
+            *threshold,
+            *max_height,
+        ))),
+        StageDef::Worms {
+            name,
+            chance,
+            max_count,
+            length,
+            radius,
+            vertical_scale,
+            max_pitch,
+        } => generator.add_stage(Box::new(WormCarver::new(WormSettings {
+            salt: name_salt(name),
+            chance: *chance,
+            max_count: *max_count,
+            min_length: length.0,
+            max_length: length.1,
+            min_radius: radius.0,
+            max_radius: radius.1,
+            vertical_scale: *vertical_scale,
+            max_pitch: *max_pitch,
+        }))),
+        StageDef::Ores { ores } => {
+            let ores = ores
+                .iter()
+                .map(|name| {
+                    let id = registry
+                        .find_ore_id(name)
+                        .unwrap_or_else(|| panic!("worldgen references unknown ore `{name}`"));
+                    let ore = registry.get_ore(id).clone();
+                    let salt = name_salt(&registry.get_block(ore.block).identifier);
+                    (ore, salt)
+                })
+                .collect();
+            generator.add_stage(Box::new(OreStage { ores }));
+        }
+        StageDef::Structures { placements } => {
+            let placements = placements
+                .iter()
+                .map(|placement| {
+                    let name = &placement.structure;
+                    let id = registry.find_structure_id(name).unwrap_or_else(|| {
+                        panic!("worldgen references unknown structure `{name}`")
+                    });
+                    Placement {
+                        structure: registry.get_structure(id).clone(),
+                        on: block_ids(registry, &placement.on),
+                        attempts: placement.attempts,
+                        chance: placement.chance,
+                    }
+                })
+                .collect();
+            generator.add_stage(Box::new(StructureStage { placements }));
+        }
+        StageDef::Decorator { block, on, chance } => {
+            generator.add_stage(Box::new(DecoratorStage {
+                decoration: Decoration {
+                    block: block_id(registry, block),
+                    on: block_ids(registry, on),
+                    chance: *chance,
+                },
+            }))
+        }
+    }
+}
+
+fn block_id(registry: &Registry, name: &str) -> BlockType {
+    registry
+        .find_block_id(name)
+        .unwrap_or_else(|| panic!("worldgen references unknown block `{name}`"))
+}
+
+fn block_ids(registry: &Registry, names: &[String]) -> Vec<BlockType> {
+    names.iter().map(|name| block_id(registry, name)).collect()
+}
@@ -0,0 +1,61 @@
+use crate::assets::Ore;
+
+use super::{rng::Rng, GenerationStage, ProtoChunk, StageKind};
+
+const ORE_SALT: u32 = 0x0e5e_0000;
+
+/// Offsets a vein may wander in from one block to the next.
+const NEIGHBOURS: [[i32; 3]; 6] = [
+    [1, 0, 0],
+    [-1, 0, 0],
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+];
+
+/// Grows veins of ore by random walk through their host blocks. Veins are seeded from the chunk
+/// they're in and stay within it.
+pub struct OreStage {
+    /// Every ore with its salt, hashed from its block's identifier.
+    pub ores: Vec<(Ore, u32)>,
+}
+
+impl GenerationStage for OreStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Ore
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let len = chunk.chunk_len as i32;
+        let origin_y = chunk.origin()[1];
+        for (ore, salt) in &self.ores {
+            let min_y = ore.min_height.max(origin_y) - origin_y;
+            let max_y = ore.max_height.min(origin_y + len - 1) - origin_y;
+            if min_y > max_y {
+                continue;
+            }
+
+            let mut rng = Rng::new(chunk.seed, chunk.chunk_pos, ORE_SALT ^ salt);
+            for _ in 0..ore.attempts {
+                let mut pos = [
+                    rng.next_below(len as u32) as i32,
+                    rng.range_u32(min_y as u32, max_y as u32) as i32,
+                    rng.next_below(len as u32) as i32,
+                ];
+
+                for _ in 0..ore.vein_size {
+                    let (x, y, z) = (pos[0] as usize, pos[1] as usize, pos[2] as usize);
+                    if ore.replaceable.contains(&chunk.get(x, y, z)) {
+                        chunk.set(x, y, z, ore.block);
+                    }
+
+                    let step = NEIGHBOURS[rng.next_below(NEIGHBOURS.len() as u32) as usize];
+                    for (p, s) in pos.iter_mut().zip(step) {
+                        *p = (*p + s).clamp(0, len - 1);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Small deterministic helpers so generation only depends on the seed and position.
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Hashes a seed, a position and a salt down to a well-mixed 64 bit value.
+pub fn hash(seed: i32, pos: [i32; 3], salt: u32) -> u64 {
+    let mut h = mix((seed as u32 as u64 | ((salt as u64) << 32)).wrapping_add(GOLDEN_GAMMA));
+    for p in pos {
+        h = mix((h ^ p as u32 as u64).wrapping_add(GOLDEN_GAMMA));
+    }
+    h
+}
+
+/// Hashes a name into a salt, so salts follow what they belong to rather than where it's listed.
+pub fn name_salt(name: &str) -> u32 {
+    // FNV-1a, which unlike std's hasher is guaranteed to stay the same between releases.
+    name.bytes()
+        .fold(0x811c_9dc5, |h, b| (h ^ b as u32).wrapping_mul(0x0100_0193))
+}
+
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// A splitmix64 stream seeded from [`hash`].
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: i32, pos: [i32; 3], salt: u32) -> Self {
+        Self(hash(seed, pos, salt))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(GOLDEN_GAMMA);
+        mix(self.0)
+    }
+
+    /// A value in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A value in `[0, n)`.
+    pub fn next_below(&mut self, n: u32) -> u32 {
+        (((self.next_u64() >> 32) * n as u64) >> 32) as u32
+    }
+
+    /// A value in `[min, max]`.
+    pub fn range_u32(&mut self, min: u32, max: u32) -> u32 {
+        min + self.next_below(max.saturating_sub(min) + 1)
+    }
+
+    /// A value in `[min, max)`.
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
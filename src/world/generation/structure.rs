@@ -0,0 +1,116 @@
+use crate::assets::{BlockType, Structure};
+
+use super::{rng::Rng, GenerationStage, ProtoChunk, StageKind};
+
+const STRUCTURE_SALT: u32 = 0x57c7_0000;
+
+pub struct Placement {
+    pub structure: Structure,
+    pub on: Vec<BlockType>,
+    pub attempts: u32,
+    pub chance: f32,
+}
+
+/// Grows structures from the surface of the chunk. Every structure is seeded from the chunk it
+/// starts in, and anything that reaches past the chunk's borders is handed to its neighbours
+/// through [`ProtoChunk::overflow`].
+pub struct StructureStage {
+    pub placements: Vec<Placement>,
+}
+
+impl GenerationStage for StructureStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Structure
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let len = chunk.chunk_len;
+        let origin = chunk.origin();
+        for (p, placement) in self.placements.iter().enumerate() {
+            let mut rng = Rng::new(chunk.seed, chunk.chunk_pos, STRUCTURE_SALT + p as u32);
+            for _ in 0..placement.attempts {
+                let x = rng.next_below(len as u32) as usize;
+                let z = rng.next_below(len as u32) as usize;
+                if rng.next_f32() >= placement.chance {
+                    continue;
+                }
+
+                let Some(y) = (0..len).rev().find(|&y| {
+                    placement.on.contains(&chunk.get(x, y, z))
+                        && chunk.get_extended(x, y + 1, z) == chunk.air
+                }) else {
+                    continue;
+                };
+
+                let anchor = [
+                    origin[0] + x as i32,
+                    origin[1] + y as i32 + 1,
+                    origin[2] + z as i32,
+                ];
+                for (offset, block) in structure_blocks(&placement.structure, &mut rng) {
+                    chunk.place(
+                        [
+                            anchor[0] + offset[0],
+                            anchor[1] + offset[1],
+                            anchor[2] + offset[2],
+                        ],
+                        block,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Lays out a structure relative to its anchor, the air block just above the ground. Blocks only
+/// ever replace air, so earlier entries win.
+fn structure_blocks(structure: &Structure, rng: &mut Rng) -> Vec<([i32; 3], BlockType)> {
+    match structure {
+        Structure::Tree {
+            trunk,
+            leaves,
+            min_height,
+            max_height,
+            canopy_radius,
+        } => {
+            let height = rng.range_u32(*min_height, *max_height) as i32;
+            let mut blocks: Vec<_> = (0..height).map(|y| ([0, y, 0], *trunk)).collect();
+
+            for y in height - 2..=height {
+                let radius = if y < height { *canopy_radius as i32 } else { 1 };
+                for z in -radius..=radius {
+                    for x in -radius..=radius {
+                        // Knock off some of the corners so the canopy isn't a perfect box.
+                        let corner = x.abs() == radius && z.abs() == radius;
+                        if corner && (radius > 1 || rng.next_f32() < 0.5) {
+                            continue;
+                        }
+                        blocks.push(([x, y, z], *leaves));
+                    }
+                }
+            }
+            blocks
+        }
+        Structure::Boulder {
+            block,
+            min_radius,
+            max_radius,
+        } => {
+            let radius = rng.range_f32(*min_radius, *max_radius);
+            let r = radius.ceil() as i32;
+            let mut blocks = vec![];
+            for z in -r..=r {
+                for y in -r..=r {
+                    for x in -r..=r {
+                        let distance = ((x * x + y * y + z * z) as f32).sqrt();
+                        if distance <= radius {
+                            blocks.push(([x, y, z], *block));
+                        }
+                    }
+                }
+            }
+            blocks
+        }
+        Structure::Template { blocks } => blocks.clone(),
+    }
+}
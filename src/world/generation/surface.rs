@@ -0,0 +1,44 @@
+use crate::assets::BlockType;
+
+use super::{GenerationStage, ProtoChunk, StageKind, APRON};
+
+/// Turns the top `depth` layers of exposed `replaceable` blocks into `top` and `filler`, or the
+/// column's biome's choice of them.
+pub struct SurfaceStage {
+    pub top: BlockType,
+    pub filler: BlockType,
+    pub depth: usize,
+    pub replaceable: Vec<BlockType>,
+}
+
+impl GenerationStage for SurfaceStage {
+    fn kind(&self) -> StageKind {
+        StageKind::Surface
+    }
+
+    fn apply(&self, chunk: &mut ProtoChunk) {
+        let len = chunk.chunk_len;
+        for z in 0..len {
+            for y in 0..len {
+                for x in 0..len {
+                    if !self.replaceable.contains(&chunk.get(x, y, z)) {
+                        continue;
+                    }
+
+                    let (top, filler, depth) = match &chunk.columns[chunk.column_idx(x, z)].biome {
+                        Some(biome) => (biome.top, biome.filler, biome.depth),
+                        None => (self.top, self.filler, self.depth),
+                    };
+
+                    for dy in 1..=depth.min(APRON) {
+                        if chunk.get_extended(x, y + dy, z) == chunk.air {
+                            let block = if dy == 1 { top } else { filler };
+                            chunk.set(x, y, z, block);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::assets::{BlockType, Registry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightmapKind {
+    /// Highest block that isn't air.
+    WorldSurface,
+    /// Highest block that things can't move through.
+    MotionBlocking,
+}
+
+/// Which blocks count towards each [`HeightmapKind`].
+pub struct HeightmapBlocks {
+    air: BlockType,
+    motion_blocking: Vec<bool>,
+}
+
+impl HeightmapBlocks {
+    pub fn from_registry(registry: &Registry) -> Self {
+        Self {
+            air: registry.get_block_id("air"),
+            motion_blocking: (0..registry.block_count())
+                .map(|id| registry.get_block(id).motion_blocking)
+                .collect(),
+        }
+    }
+
+    pub fn counts(&self, kind: HeightmapKind, block: BlockType) -> bool {
+        block != self.air
+            && match kind {
+                HeightmapKind::WorldSurface => true,
+                HeightmapKind::MotionBlocking => self.motion_blocking[block],
+            }
+    }
+}
+
+/// The local height of the highest counted block in each column of a single chunk, if there is
+/// one. Columns spanning several chunks are answered by [`super::VoxelWorld`].
+#[derive(Debug, Clone, Default, bitcode::Encode, bitcode::Decode)]
+pub struct ChunkHeightmaps {
+    world_surface: Vec<Option<u16>>,
+    motion_blocking: Vec<Option<u16>>,
+}
+
+impl ChunkHeightmaps {
+    pub fn compute(voxels: &[BlockType], chunk_len: usize, blocks: &HeightmapBlocks) -> Self {
+        let mut heightmaps = Self {
+            world_surface: vec![None; chunk_len * chunk_len],
+            motion_blocking: vec![None; chunk_len * chunk_len],
+        };
+        for z in 0..chunk_len {
+            for x in 0..chunk_len {
+                heightmaps.scan_column(voxels, chunk_len, blocks, x, z);
+            }
+        }
+        heightmaps
+    }
+
+    pub fn get(&self, kind: HeightmapKind, x: usize, z: usize, chunk_len: usize) -> Option<u16> {
+        self.map(kind)[x + z * chunk_len]
+    }
+
+    /// Brings the heightmaps up to date after the voxel at `(x, y, z)` changed.
+    pub fn update(
+        &mut self,
+        voxels: &[BlockType],
+        chunk_len: usize,
+        blocks: &HeightmapBlocks,
+        x: usize,
+        y: usize,
+        z: usize,
+    ) {
+        let block = voxels[x + y * chunk_len + z * chunk_len * chunk_len];
+        let column = x + z * chunk_len;
+        for kind in [HeightmapKind::WorldSurface, HeightmapKind::MotionBlocking] {
+            let top = self.map(kind)[column];
+            if blocks.counts(kind, block) {
+                if top.is_none_or(|top| y as u16 > top) {
+                    self.map_mut(kind)[column] = Some(y as u16);
+                }
+            } else if top == Some(y as u16) {
+                self.map_mut(kind)[column] = (0..y)
+                    .rev()
+                    .find(|&y| {
+                        blocks.counts(kind, voxels[x + y * chunk_len + z * chunk_len * chunk_len])
+                    })
+                    .map(|y| y as u16);
+            }
+        }
+    }
+
+    fn scan_column(
+        &mut self,
+        voxels: &[BlockType],
+        chunk_len: usize,
+        blocks: &HeightmapBlocks,
+        x: usize,
+        z: usize,
+    ) {
+        let column = x + z * chunk_len;
+        for kind in [HeightmapKind::WorldSurface, HeightmapKind::MotionBlocking] {
+            self.map_mut(kind)[column] = (0..chunk_len)
+                .rev()
+                .find(|&y| {
+                    blocks.counts(kind, voxels[x + y * chunk_len + z * chunk_len * chunk_len])
+                })
+                .map(|y| y as u16);
+        }
+    }
+
+    fn map(&self, kind: HeightmapKind) -> &Vec<Option<u16>> {
+        match kind {
+            HeightmapKind::WorldSurface => &self.world_surface,
+            HeightmapKind::MotionBlocking => &self.motion_blocking,
+        }
+    }
+
+    fn map_mut(&mut self, kind: HeightmapKind) -> &mut Vec<Option<u16>> {
+        match kind {
+            HeightmapKind::WorldSurface => &mut self.world_surface,
+            HeightmapKind::MotionBlocking => &mut self.motion_blocking,
+        }
+    }
+}
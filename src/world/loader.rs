@@ -0,0 +1,156 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::screens::Screen;
+
+use super::{
+    chunk::{Chunk, ChunkNeedsLoading, ChunkNeedsSaving, ChunkNeedsUnloading},
+    voxel_world::VoxelWorld,
+};
+
+pub struct ChunkLoaderPlugin;
+
+impl Plugin for ChunkLoaderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadedChunks>();
+        app.add_systems(OnEnter(Screen::Gameplay), reset_loaded_chunks);
+    }
+}
+
+/// Keeps every chunk within `radius` chunks of the entity resident.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ChunkLoader {
+    pub radius: u32,
+}
+
+/// The cube of chunks a loader last requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LoadedRegion {
+    center: [i32; 3],
+    radius: i32,
+}
+
+impl LoadedRegion {
+    fn for_each(&self, mut f: impl FnMut([i32; 3])) {
+        let r = self.radius;
+        for z in -r..=r {
+            for y in -r..=r {
+                for x in -r..=r {
+                    f([self.center[0] + x, self.center[1] + y, self.center[2] + z]);
+                }
+            }
+        }
+    }
+}
+
+/// Reference counts for every chunk requested by a [`ChunkLoader`]. A chunk is only unloaded once
+/// no loader covers it anymore.
+#[derive(Resource, Default)]
+pub struct LoadedChunks {
+    regions: HashMap<Entity, LoadedRegion>,
+    ref_counts: HashMap<[i32; 3], u32>,
+    entities: HashMap<[i32; 3], Entity>,
+}
+
+impl LoadedChunks {
+    pub fn get_entity(&self, chunk_pos: &[i32; 3]) -> Option<Entity> {
+        self.entities.get(chunk_pos).copied()
+    }
+
+    pub fn is_requested(&self, chunk_pos: &[i32; 3]) -> bool {
+        self.ref_counts.contains_key(chunk_pos)
+    }
+
+    pub(super) fn forget_entity(&mut self, chunk_pos: &[i32; 3]) {
+        self.entities.remove(chunk_pos);
+    }
+}
+
+fn reset_loaded_chunks(mut commands: Commands) {
+    commands.insert_resource(LoadedChunks::default());
+}
+
+pub(super) fn load_unload_chunks_around_loaders(
+    mut commands: Commands,
+    storage: Res<VoxelWorld>,
+    mut loaded: ResMut<LoadedChunks>,
+    mut removed_loaders: RemovedComponents<ChunkLoader>,
+    query_loaders: Query<(Entity, &GlobalTransform, &ChunkLoader)>,
+    query_chunks: Query<&Chunk>,
+) {
+    let mut requested = vec![];
+    let mut released = vec![];
+
+    for id in removed_loaders.read() {
+        if let Some(region) = loaded.regions.remove(&id) {
+            region.for_each(|pos| released.push(pos));
+        }
+    }
+
+    for (id, transform, loader) in &query_loaders {
+        let region = LoadedRegion {
+            center: storage.world_to_chunk_pos(transform.translation()),
+            radius: loader.radius as i32,
+        };
+
+        match loaded.regions.insert(id, region) {
+            Some(old_region) if old_region == region => continue,
+            Some(old_region) => old_region.for_each(|pos| released.push(pos)),
+            None => {}
+        }
+        region.for_each(|pos| requested.push(pos));
+    }
+
+    // Requests are handled first so chunks shared by a loader's old and new regions never drop to
+    // zero and get churned.
+    for pos in requested {
+        let count = loaded.ref_counts.entry(pos).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            continue;
+        }
+
+        if let Some(id) = loaded.get_entity(&pos) {
+            commands.entity(id).remove::<ChunkNeedsUnloading>();
+            continue;
+        }
+
+        let chunk_len = storage.chunk_len as f32;
+        let id = commands
+            .spawn((
+                StateScoped(Screen::Gameplay),
+                Chunk {
+                    world_pos: pos,
+                    dirty: false,
+                },
+                ChunkNeedsLoading,
+                Transform::from_xyz(
+                    pos[0] as f32 * chunk_len,
+                    pos[1] as f32 * chunk_len,
+                    pos[2] as f32 * chunk_len,
+                ),
+            ))
+            .id();
+        loaded.entities.insert(pos, id);
+    }
+
+    for pos in released {
+        let Some(count) = loaded.ref_counts.get_mut(&pos) else {
+            continue;
+        };
+
+        *count -= 1;
+        if *count > 0 {
+            continue;
+        }
+
+        loaded.ref_counts.remove(&pos);
+        let Some(id) = loaded.get_entity(&pos) else {
+            continue;
+        };
+
+        if query_chunks.get(id).is_ok_and(|chunk| chunk.dirty) {
+            commands.entity(id).insert(ChunkNeedsSaving);
+        }
+        commands.entity(id).insert(ChunkNeedsUnloading);
+    }
+}
@@ -0,0 +1,91 @@
+use std::{fs, io};
+
+use bevy::prelude::*;
+
+use super::save_file::LoadError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum GameMode {
+    /// Every block is available and placing them is free.
+    #[default]
+    Creative,
+    /// Blocks have to be collected by breaking them, and placing them uses them up.
+    Survival,
+}
+
+impl GameMode {
+    /// The mode asked for on the command line with `--creative` or `--survival`, which new worlds
+    /// are created in.
+    pub fn from_args() -> Option<Self> {
+        std::env::args().find_map(|arg| match arg.as_str() {
+            "--creative" => Some(Self::Creative),
+            "--survival" => Some(Self::Survival),
+            _ => None,
+        })
+    }
+}
+
+/// Written at the top of `world.ron`, since editing it is the only other way to change these.
+const METADATA_HEADER: &str = "\
+// seed: Seed the terrain is generated from.
+// generator: Identifier of the `*.worldgen.ron` preset the world generates with.
+// spawn: Where players appear, found when the world is first created.
+// game_mode: Creative, or Survival to have to collect blocks before placing them.
+";
+
+/// Settings a world is created with, kept in `world.ron` in its save directory.
+#[derive(Resource, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WorldMetadata {
+    pub seed: i32,
+    /// Identifier of the `*.worldgen.ron` preset the world generates with.
+    pub generator: String,
+    /// Where players appear in the world, found when the world is first created.
+    #[serde(default)]
+    pub spawn: Option<[i32; 3]>,
+    #[serde(default)]
+    pub game_mode: GameMode,
+}
+
+impl Default for WorldMetadata {
+    fn default() -> Self {
+        Self {
+            seed: 1338,
+            generator: "default".to_string(),
+            spawn: None,
+            game_mode: GameMode::default(),
+        }
+    }
+}
+
+impl WorldMetadata {
+    /// Reads the world's metadata, or `None` if the world hasn't been created yet.
+    pub fn load(save_dir: &str) -> Result<Option<Self>, LoadError> {
+        let path = format!("{save_dir}/world.ron");
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(LoadError::Read(path, e)),
+        };
+        ron::from_str(&contents)
+            .map(Some)
+            .map_err(|e| LoadError::Parse(path, e.to_string()))
+    }
+
+    /// Position of the feet of a player standing at the spawn point.
+    pub fn spawn_point(&self) -> Vec3 {
+        let [x, y, z] = self.spawn.unwrap_or_default();
+        Vec3::new(x as f32 + 0.5, y as f32, z as f32 + 0.5)
+    }
+
+    pub fn save(&self, save_dir: &str) {
+        if fs::create_dir_all(save_dir).is_err() {
+            return;
+        }
+
+        let path = format!("{save_dir}/world.ron");
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).unwrap();
+        if let Err(e) = fs::write(&path, format!("{METADATA_HEADER}{contents}")) {
+            warn!("failed to write {path}: {e}");
+        }
+    }
+}
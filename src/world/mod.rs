@@ -1,14 +1,48 @@
+mod block_entity;
+mod budget;
 mod chunk;
+mod collision;
+mod generation;
+mod heightmap;
+mod loader;
+mod metadata;
+mod pending;
+mod physics;
+mod save_file;
+mod spawn;
 mod voxel_world;
 
 use bevy::prelude::*;
 
-pub use {chunk::Chunk, voxel_world::VoxelWorld};
+pub use {
+    block_entity::{BlockEntity, BlockEntityData, BlockEntityRemoved, SpawnBlockEntity},
+    budget::ChunkBudget,
+    chunk::Chunk,
+    loader::ChunkLoader,
+    metadata::{GameMode, WorldMetadata},
+    physics::{VoxelBody, VoxelCollider, VoxelPhysicsSet},
+    save_file::{set_aside, LoadError},
+    spawn::SpawnMoved,
+    voxel_world::VoxelWorld,
+};
+
+/// Inserts the world's resources on entering gameplay. Anything else that needs them then should
+/// run after this.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WorldSetup;
 
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((chunk::ChunkPlugin, voxel_world::VoxelWorldPlugin));
+        app.add_plugins((
+            block_entity::BlockEntityPlugin,
+            budget::ChunkBudgetPlugin,
+            chunk::ChunkPlugin,
+            loader::ChunkLoaderPlugin,
+            physics::VoxelPhysicsPlugin,
+            spawn::SpawnPlugin,
+            voxel_world::VoxelWorldPlugin,
+        ));
     }
 }
@@ -0,0 +1,119 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+};
+
+use bevy::{prelude::*, utils::HashMap};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+use crate::assets::BlockType;
+
+use super::save_file::set_aside;
+
+/// Blocks that structures spilled into chunks which didn't exist yet. They're written in once the
+/// chunk is generated or loaded.
+#[derive(Resource, Default)]
+pub struct PendingBlocks {
+    chunks: HashMap<[i32; 3], Vec<(usize, BlockType)>>,
+    dirty: bool,
+}
+
+impl PendingBlocks {
+    pub fn push(&mut self, chunk_pos: [i32; 3], blocks: Vec<(usize, BlockType)>) {
+        self.chunks.entry(chunk_pos).or_default().extend(blocks);
+        self.dirty = true;
+    }
+
+    /// Writes anything pending for the chunk into its voxels, returning whether any changed.
+    pub fn apply(
+        &mut self,
+        chunk_pos: &[i32; 3],
+        voxels: &mut [BlockType],
+        air: BlockType,
+    ) -> bool {
+        let Some(blocks) = self.chunks.remove(chunk_pos) else {
+            return false;
+        };
+
+        self.dirty = true;
+        apply_blocks(voxels, &blocks, air)
+    }
+
+    /// Reads what's pending from the save. A file that can't be read is moved aside, since
+    /// saving would otherwise replace it with nothing.
+    pub fn load(save_dir: &str) -> Self {
+        let path = format!("{save_dir}/pending.dat");
+        let f = match File::open(&path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                warn!("failed to open {path}: {e}");
+                set_aside(&path);
+                return Self::default();
+            }
+        };
+
+        let mut decompressed_buffer = vec![];
+        let mut decoder = DeflateDecoder::new(f);
+        if let Err(e) = decoder.read_to_end(&mut decompressed_buffer) {
+            warn!("failed to read {path}: {e}");
+            set_aside(&path);
+            return Self::default();
+        }
+
+        let Ok(chunks) =
+            bitcode::decode::<Vec<([i32; 3], Vec<(usize, BlockType)>)>>(&decompressed_buffer)
+        else {
+            warn!("failed to decode {path}");
+            set_aside(&path);
+            return Self::default();
+        };
+
+        Self {
+            chunks: chunks.into_iter().collect(),
+            dirty: false,
+        }
+    }
+
+    pub fn save(&mut self, save_dir: &str) {
+        if !self.dirty {
+            return;
+        }
+
+        if fs::create_dir_all(save_dir).is_err() {
+            return;
+        }
+
+        let chunks: Vec<_> = self
+            .chunks
+            .iter()
+            .map(|(pos, blocks)| (*pos, blocks.clone()))
+            .collect();
+        let buffer = bitcode::encode(&chunks);
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&buffer).unwrap();
+        let compressed_buffer = encoder.finish().unwrap();
+
+        let mut f = File::create(format!("{save_dir}/pending.dat")).unwrap();
+        f.write_all(&compressed_buffer).unwrap();
+
+        self.dirty = false;
+    }
+}
+
+/// Writes `blocks` into any air voxels they land on, returning whether any changed.
+pub fn apply_blocks(
+    voxels: &mut [BlockType],
+    blocks: &[(usize, BlockType)],
+    air: BlockType,
+) -> bool {
+    let mut changed = false;
+    for &(idx, block) in blocks {
+        if voxels[idx] == air {
+            voxels[idx] = block;
+            changed = true;
+        }
+    }
+    changed
+}
@@ -0,0 +1,258 @@
+use bevy::{
+    math::bounding::{Aabb3d, BoundingVolume},
+    prelude::*,
+};
+
+use crate::screens::Screen;
+
+use super::VoxelWorld;
+
+/// How far a sneaking body looks down for ground before treating the way ahead as an edge.
+const EDGE_PROBE: f32 = 0.6;
+/// How much a sneaking body's motion is shortened by at a time while searching for ground.
+const EDGE_STEP: f32 = 0.05;
+
+pub struct VoxelPhysicsPlugin;
+
+impl Plugin for VoxelPhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedFirst,
+            restore_physics_translations.run_if(in_state(Screen::Gameplay)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            step_bodies
+                .in_set(VoxelPhysicsSet)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+        app.add_systems(
+            FixedPostUpdate,
+            record_physics_translations.run_if(in_state(Screen::Gameplay)),
+        );
+        app.add_systems(
+            PostUpdate,
+            (add_interpolation, interpolate_translations)
+                .chain()
+                .before(TransformSystem::TransformPropagate)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+/// Moves every [`VoxelBody`] on the fixed timestep. Anything steering a body should run before
+/// this.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VoxelPhysicsSet;
+
+/// An axis aligned box that collides with the world, relative to the entity's translation.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct VoxelCollider {
+    pub half_extents: Vec3,
+    pub offset: Vec3,
+}
+
+impl VoxelCollider {
+    pub fn aabb(&self, translation: Vec3) -> Aabb3d {
+        Aabb3d::new(translation + self.offset, self.half_extents)
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct VoxelBody {
+    pub velocity: Vec3,
+    pub gravity: f32,
+    pub terminal_velocity: f32,
+    /// Tallest ledge the body steps up onto without having to jump, while on the ground.
+    pub step_height: f32,
+    /// How quickly horizontal velocity dies off on the ground.
+    pub friction: f32,
+    /// Vertical speed while on something climbable, which replaces gravity.
+    pub climb_speed: f32,
+    /// Sneaking bodies won't walk off the edge of whatever they're standing on.
+    pub sneaking: bool,
+    pub on_ground: bool,
+    /// Set while overlapping something climbable. Vertical velocity is left for whatever steers
+    /// the body, up to `climb_speed`.
+    pub climbing: bool,
+    /// Kinematic bodies are moved by hand and left alone by the simulation.
+    pub kinematic: bool,
+}
+
+impl Default for VoxelBody {
+    fn default() -> Self {
+        Self {
+            velocity: Vec3::ZERO,
+            gravity: 28.,
+            terminal_velocity: 60.,
+            step_height: 0.,
+            friction: 8.,
+            climb_speed: 3.,
+            sneaking: false,
+            on_ground: false,
+            climbing: false,
+            kinematic: false,
+        }
+    }
+}
+
+/// The translations of a [`VoxelBody`] at the last two fixed ticks. Between ticks its transform is
+/// drawn part way from one to the other, so it moves smoothly at any frame rate. Anything moving
+/// the body by hand outside of the fixed timestep teleports it there instead.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PhysicsInterpolation {
+    previous: Vec3,
+    current: Vec3,
+    /// What the transform was last set to, to tell when something else has moved it.
+    rendered: Vec3,
+}
+
+impl PhysicsInterpolation {
+    fn snap_to(&mut self, translation: Vec3) {
+        self.previous = translation;
+        self.current = translation;
+        self.rendered = translation;
+    }
+}
+
+fn add_interpolation(
+    mut commands: Commands,
+    query_bodies: Query<(Entity, &Transform), (With<VoxelBody>, Without<PhysicsInterpolation>)>,
+) {
+    for (id, transform) in &query_bodies {
+        let translation = transform.translation;
+        commands.entity(id).insert(PhysicsInterpolation {
+            previous: translation,
+            current: translation,
+            rendered: translation,
+        });
+    }
+}
+
+/// Puts bodies back where the simulation left them before it steps again.
+fn restore_physics_translations(
+    mut query_bodies: Query<(&mut Transform, &mut PhysicsInterpolation)>,
+) {
+    for (mut transform, mut interpolation) in &mut query_bodies {
+        if transform.translation != interpolation.rendered {
+            interpolation.snap_to(transform.translation);
+        } else {
+            transform.translation = interpolation.current;
+            interpolation.rendered = interpolation.current;
+        }
+    }
+}
+
+fn record_physics_translations(mut query_bodies: Query<(&Transform, &mut PhysicsInterpolation)>) {
+    for (transform, mut interpolation) in &mut query_bodies {
+        interpolation.previous = interpolation.current;
+        interpolation.current = transform.translation;
+        interpolation.rendered = transform.translation;
+    }
+}
+
+fn interpolate_translations(
+    time: Res<Time<Fixed>>,
+    mut query_bodies: Query<(&mut Transform, &mut PhysicsInterpolation)>,
+) {
+    let t = time.overstep_fraction();
+    for (mut transform, mut interpolation) in &mut query_bodies {
+        if transform.translation != interpolation.rendered {
+            interpolation.snap_to(transform.translation);
+            continue;
+        }
+
+        let translation = interpolation.previous.lerp(interpolation.current, t);
+        transform.translation = translation;
+        interpolation.rendered = translation;
+    }
+}
+
+fn step_bodies(
+    time: Res<Time>,
+    storage: Res<VoxelWorld>,
+    mut query_bodies: Query<(&mut Transform, &mut VoxelBody, &VoxelCollider)>,
+) {
+    let dt = time.delta_secs();
+    for (mut transform, mut body, collider) in &mut query_bodies {
+        if body.kinematic {
+            continue;
+        }
+
+        if body.on_ground && body.friction > 0. {
+            let t = (-body.friction * dt).exp();
+            body.velocity.x *= t;
+            body.velocity.z *= t;
+        }
+
+        let aabb = collider.aabb(transform.translation);
+        body.climbing = storage.overlaps_climbable(aabb);
+        if body.climbing {
+            body.velocity.y = body.velocity.y.clamp(-body.climb_speed, body.climb_speed);
+        } else {
+            body.velocity.y = (body.velocity.y - body.gravity * dt).max(-body.terminal_velocity);
+        }
+
+        let mut motion = body.velocity * dt;
+        if body.sneaking && body.on_ground {
+            stop_at_edges(&storage, aabb, &mut motion);
+            if motion.x == 0. {
+                body.velocity.x = 0.;
+            }
+            if motion.z == 0. {
+                body.velocity.z = 0.;
+            }
+        }
+        let mut moved = storage.sweep(aabb, motion);
+
+        // Try again from a step higher, and keep whichever got further.
+        let blocked = moved.x != motion.x || moved.z != motion.z;
+        if body.on_ground && blocked && body.step_height > 0. {
+            let up = storage.sweep(aabb, Vec3::Y * body.step_height);
+            let raised = aabb.translated_by(up);
+            let across = storage.sweep(raised, Vec3::new(motion.x, 0., motion.z));
+            let down = storage.sweep(raised.translated_by(across), Vec3::NEG_Y * up.y);
+            let stepped = up + across + down;
+            if stepped.xz().length_squared() > moved.xz().length_squared() {
+                moved = stepped;
+            }
+        }
+
+        body.on_ground = motion.y < 0. && moved.y > motion.y;
+        if moved.x != motion.x {
+            body.velocity.x = 0.;
+        }
+        if moved.y != motion.y {
+            body.velocity.y = 0.;
+        }
+        if moved.z != motion.z {
+            body.velocity.z = 0.;
+        }
+
+        transform.translation += moved;
+    }
+}
+
+/// Shortens horizontal `motion` until `aabb` would still have ground beneath it.
+fn stop_at_edges(storage: &VoxelWorld, aabb: Aabb3d, motion: &mut Vec3) {
+    let supported =
+        |x: f32, z: f32| storage.overlaps(aabb.translated_by(Vec3::new(x, -EDGE_PROBE, z)));
+    let shorten = |d: f32| {
+        if d.abs() <= EDGE_STEP {
+            0.
+        } else {
+            d - EDGE_STEP * d.signum()
+        }
+    };
+
+    while motion.x != 0. && !supported(motion.x, 0.) {
+        motion.x = shorten(motion.x);
+    }
+    while motion.z != 0. && !supported(0., motion.z) {
+        motion.z = shorten(motion.z);
+    }
+    while motion.x != 0. && motion.z != 0. && !supported(motion.x, motion.z) {
+        motion.x = shorten(motion.x);
+        motion.z = shorten(motion.z);
+    }
+}
@@ -0,0 +1,43 @@
+use std::{fmt, fs, io, path::Path};
+
+use bevy::prelude::*;
+
+/// Why a save file that's there couldn't be loaded. Whatever was in it shouldn't be overwritten.
+#[derive(Debug)]
+pub enum LoadError {
+    Read(String, io::Error),
+    Parse(String, String),
+}
+
+impl LoadError {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Read(path, _) | Self::Parse(path, _) => path,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Read(path, e) => write!(f, "failed to read {path}: {e}"),
+            Self::Parse(path, e) => write!(f, "failed to parse {path}: {e}"),
+        }
+    }
+}
+
+/// Moves a save file that couldn't be loaded to `<path>.bak`, or the first free `<path>.<n>.bak`,
+/// so starting it over doesn't destroy it.
+pub fn set_aside(path: &str) {
+    let backup = (1..)
+        .map(|n| match n {
+            1 => format!("{path}.bak"),
+            n => format!("{path}.{n}.bak"),
+        })
+        .find(|backup| !Path::new(backup).exists())
+        .unwrap();
+    match fs::rename(path, &backup) {
+        Ok(()) => warn!("moved {path} to {backup}"),
+        Err(e) => warn!("failed to move {path} to {backup}: {e}"),
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::{math::bounding::Aabb3d, prelude::*};
+
+use crate::{assets::BlockType, screens::Screen};
+
+use super::{
+    generation::{ProtoChunk, TerrainGenerator},
+    heightmap::{HeightmapBlocks, HeightmapKind},
+    metadata::WorldMetadata,
+    voxel_world::VoxelWorld,
+};
+
+pub struct SpawnPlugin;
+
+impl Plugin for SpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnMoved>();
+        app.add_systems(
+            Update,
+            check_spawn
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<SpawnNeedsChecking>)),
+        );
+    }
+}
+
+/// Present while a newly found spawn point still has to be checked against the loaded world.
+/// [`find_spawn`] generates chunks on their own, so it can't see structures that neighbouring
+/// chunks spill over it, like a tree growing into the spot.
+#[derive(Resource)]
+pub struct SpawnNeedsChecking;
+
+/// Sent when the spawn point had to be moved after it was chosen.
+#[derive(Event)]
+pub struct SpawnMoved {
+    /// Position of the feet of a player standing at the new spawn point.
+    pub to: Vec3,
+}
+
+/// How many chunk columns out from the origin to look for somewhere to stand.
+const SEARCH_RADIUS: i32 = 2;
+/// Chunk heights searched, top to bottom.
+const SEARCH_MIN_Y: i32 = -4;
+const SEARCH_MAX_Y: i32 = 4;
+
+/// Generates chunk columns around the origin until it finds solid ground with two blocks of air
+/// above it, returning the position of the lower air block.
+///
+/// Runs while the world is being set up, so each column is only generated down to its surface
+/// rather than through every searched height.
+pub fn find_spawn(
+    generator: &dyn TerrainGenerator,
+    blocks: &HeightmapBlocks,
+    seed: i32,
+    chunk_len: usize,
+    air: BlockType,
+) -> Option<IVec3> {
+    let mut chunk_columns = vec![];
+    for cz in -SEARCH_RADIUS..=SEARCH_RADIUS {
+        for cx in -SEARCH_RADIUS..=SEARCH_RADIUS {
+            chunk_columns.push(IVec2::new(cx, cz));
+        }
+    }
+    chunk_columns.sort_by_key(|c| c.length_squared());
+
+    let len = chunk_len as i32;
+    let mut columns = vec![];
+    for z in 0..len {
+        for x in 0..len {
+            columns.push(IVec2::new(x, z));
+        }
+    }
+
+    for chunk_column in chunk_columns {
+        let origin = chunk_column * len;
+        // Columns nearest the origin first.
+        columns.sort_by_key(|c| (origin + *c).length_squared());
+
+        // Top to bottom, so `stack[i]` is `i` chunks down from the top.
+        let mut stack = vec![];
+        for cy in (SEARCH_MIN_Y..=SEARCH_MAX_Y).rev() {
+            let mut chunk =
+                ProtoChunk::new([chunk_column.x, cy, chunk_column.y], chunk_len, seed, air);
+            generator.generate(&mut chunk);
+            stack.push(chunk);
+
+            for column in &columns {
+                if let Some(y) = find_standing_height(&stack, blocks, column.x, column.y) {
+                    return Some(IVec3::new(origin.x + column.x, y, origin.y + column.y));
+                }
+            }
+
+            // Chunks further down can't change where any column's surface is.
+            if columns
+                .iter()
+                .all(|c| surface_depth(&stack, blocks, c.x, c.y).is_some())
+            {
+                break;
+            }
+        }
+    }
+
+    None
+}
+
+fn get_at_depth(stack: &[ProtoChunk], x: usize, z: usize, depth: usize) -> BlockType {
+    let len = stack[0].chunk_len;
+    stack[depth / len].get(x, len - 1 - depth % len, z)
+}
+
+/// How many blocks down from the top of the stack the column's highest motion blocking block is.
+fn surface_depth(stack: &[ProtoChunk], blocks: &HeightmapBlocks, x: i32, z: i32) -> Option<usize> {
+    let (x, z) = (x as usize, z as usize);
+    (0..stack.len() * stack[0].chunk_len).find(|&depth| {
+        blocks.counts(
+            HeightmapKind::MotionBlocking,
+            get_at_depth(stack, x, z, depth),
+        )
+    })
+}
+
+fn find_standing_height(
+    stack: &[ProtoChunk],
+    blocks: &HeightmapBlocks,
+    x: i32,
+    z: i32,
+) -> Option<i32> {
+    let depth = surface_depth(stack, blocks, x, z)?;
+    let get = |depth| get_at_depth(stack, x as usize, z as usize, depth);
+    // Whatever is above the searched chunks might not be air.
+    if depth < 2 || get(depth - 1) != stack[0].air || get(depth - 2) != stack[0].air {
+        return None;
+    }
+
+    let len = stack[0].chunk_len;
+    let top = stack[0].origin()[1] + len as i32 - 1;
+    Some(top - depth as i32 + 1)
+}
+
+/// Once every chunk that could spill into the spawn point's chunk is loaded, moves the spawn
+/// point up onto whatever ended up on top of it, if anything did.
+fn check_spawn(
+    mut commands: Commands,
+    storage: Res<VoxelWorld>,
+    mut metadata: ResMut<WorldMetadata>,
+    mut moved_events: EventWriter<SpawnMoved>,
+) {
+    let spawn = IVec3::from_array(metadata.spawn.unwrap_or_default());
+    let (chunk_pos, _) = storage.split_pos(spawn);
+    let chunk_pos = IVec3::from_array(chunk_pos);
+    for z in -1..=1 {
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let neighbour = chunk_pos + IVec3::new(x, y, z);
+                if storage.get_chunk(&neighbour.to_array()).is_none() {
+                    return;
+                }
+            }
+        }
+    }
+
+    commands.remove_resource::<SpawnNeedsChecking>();
+    // The two blocks a player stands in.
+    let space = Aabb3d::new(
+        spawn.as_vec3() + Vec3::new(0.5, 1., 0.5),
+        Vec3::new(0.5, 1., 0.5),
+    );
+    if !storage.overlaps(space) {
+        return;
+    }
+    let Some(height) = storage.height(HeightmapKind::MotionBlocking, spawn.x, spawn.z) else {
+        return;
+    };
+
+    metadata.spawn = Some([spawn.x, height + 1, spawn.z]);
+    metadata.save(&storage.save_dir());
+    info!("spawn point was blocked, moved it up to {}", height + 1);
+    moved_events.send(SpawnMoved {
+        to: metadata.spawn_point(),
+    });
+}
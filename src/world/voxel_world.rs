@@ -1,31 +1,49 @@
 use std::time::Duration;
 
 use bevy::{prelude::*, utils::HashMap};
-use fastnoise2::SafeNode;
 
-use crate::{assets::BlockType, screens::Screen, AppSet};
+use crate::{
+    assets::{BlockType, Registry},
+    screens::Screen,
+};
+
+use super::{
+    collision::BlockCollision,
+    generation::WorldGenerator,
+    heightmap::{ChunkHeightmaps, HeightmapBlocks, HeightmapKind},
+    metadata::{GameMode, WorldMetadata},
+    pending::PendingBlocks,
+    spawn::{find_spawn, SpawnNeedsChecking},
+    WorldSetup,
+};
 
 pub struct VoxelWorldPlugin;
 
 impl Plugin for VoxelWorldPlugin {
     fn build(&self, app: &mut App) {
-        // !HACK: Putting this in TickTimers is yuck
-        app.add_systems(OnEnter(Screen::Gameplay), setup.in_set(AppSet::TickTimers));
+        app.add_systems(OnEnter(Screen::Gameplay), setup.in_set(WorldSetup));
     }
 }
 
 #[derive(Resource)]
 pub struct VoxelWorld {
     pub world_name: String,
-    pub terrain_noise: SafeNode,
-    pub terrain_frequency: f32,
     pub terrain_seed: i32,
     pub chunk_len: usize,
     pub voxels: HashMap<[i32; 3], Vec<BlockType>>,
     pub save_timer: Timer,
+    heightmaps: HashMap<[i32; 3], ChunkHeightmaps>,
+    /// Loaded chunk heights of every chunk column, highest first.
+    column_chunks: HashMap<[i32; 2], Vec<i32>>,
+    heightmap_blocks: HeightmapBlocks,
+    pub(super) block_collision: BlockCollision,
 }
 
 impl VoxelWorld {
+    pub fn save_dir(&self) -> String {
+        format!("./saves/{}", self.world_name)
+    }
+
     pub fn get_voxel(
         &self,
         chunk_pos: &[i32; 3],
@@ -47,8 +65,20 @@ impl VoxelWorld {
         block_type: BlockType,
     ) {
         let idx = self.local_pos_to_idx(local_x, local_y, local_z);
-        if let Some(chunk) = self.voxels.get_mut(chunk_pos) {
-            chunk[idx] = block_type;
+        let Some(chunk) = self.voxels.get_mut(chunk_pos) else {
+            return;
+        };
+
+        chunk[idx] = block_type;
+        if let Some(heightmaps) = self.heightmaps.get_mut(chunk_pos) {
+            heightmaps.update(
+                chunk,
+                self.chunk_len,
+                &self.heightmap_blocks,
+                local_x,
+                local_y,
+                local_z,
+            );
         }
     }
 
@@ -56,21 +86,145 @@ impl VoxelWorld {
         self.voxels.get(chunk_pos)
     }
 
+    /// Changes made through this bypass the heightmaps, follow up with
+    /// [`Self::refresh_heightmaps`].
     pub fn get_chunk_mut(&mut self, chunk_pos: &[i32; 3]) -> Option<&mut Vec<BlockType>> {
         self.voxels.get_mut(chunk_pos)
     }
 
+    pub fn get_heightmaps(&self, chunk_pos: &[i32; 3]) -> Option<&ChunkHeightmaps> {
+        self.heightmaps.get(chunk_pos)
+    }
+
     pub fn load_chunk(&mut self, chunk_pos: &[i32; 3], chunk_voxels: Vec<BlockType>) {
+        let heightmaps =
+            ChunkHeightmaps::compute(&chunk_voxels, self.chunk_len, &self.heightmap_blocks);
+        self.load_chunk_with_heightmaps(chunk_pos, chunk_voxels, heightmaps);
+    }
+
+    /// Like [`Self::load_chunk`], for when the heightmaps are already known.
+    pub fn load_chunk_with_heightmaps(
+        &mut self,
+        chunk_pos: &[i32; 3],
+        chunk_voxels: Vec<BlockType>,
+        heightmaps: ChunkHeightmaps,
+    ) {
         assert_eq!(
             chunk_voxels.len(),
             self.chunk_len * self.chunk_len * self.chunk_len
         );
 
-        self.voxels.insert(*chunk_pos, chunk_voxels);
+        if self.voxels.insert(*chunk_pos, chunk_voxels).is_none() {
+            let column = self
+                .column_chunks
+                .entry([chunk_pos[0], chunk_pos[2]])
+                .or_default();
+            let idx = column.partition_point(|&y| y > chunk_pos[1]);
+            column.insert(idx, chunk_pos[1]);
+        }
+        self.heightmaps.insert(*chunk_pos, heightmaps);
     }
 
     pub fn unload_chunk(&mut self, chunk_pos: &[i32; 3]) {
-        self.voxels.remove(chunk_pos);
+        if self.voxels.remove(chunk_pos).is_none() {
+            return;
+        }
+
+        self.heightmaps.remove(chunk_pos);
+        let column_pos = [chunk_pos[0], chunk_pos[2]];
+        if let Some(column) = self.column_chunks.get_mut(&column_pos) {
+            column.retain(|&y| y != chunk_pos[1]);
+            if column.is_empty() {
+                self.column_chunks.remove(&column_pos);
+            }
+        }
+    }
+
+    pub fn refresh_heightmaps(&mut self, chunk_pos: &[i32; 3]) {
+        if let Some(voxels) = self.voxels.get(chunk_pos) {
+            let heightmaps =
+                ChunkHeightmaps::compute(voxels, self.chunk_len, &self.heightmap_blocks);
+            self.heightmaps.insert(*chunk_pos, heightmaps);
+        }
+    }
+
+    /// World height of the highest block in a column that isn't air, among loaded chunks.
+    pub fn surface_height(&self, x: i32, z: i32) -> Option<i32> {
+        self.height(HeightmapKind::WorldSurface, x, z)
+    }
+
+    /// World height of the highest block of `kind` in a column, among loaded chunks.
+    pub fn height(&self, kind: HeightmapKind, x: i32, z: i32) -> Option<i32> {
+        let len = self.chunk_len as i32;
+        let (cx, cz) = (x.div_euclid(len), z.div_euclid(len));
+        let (local_x, local_z) = (x.rem_euclid(len) as usize, z.rem_euclid(len) as usize);
+        self.column_chunks.get(&[cx, cz])?.iter().find_map(|&cy| {
+            let top =
+                self.heightmaps
+                    .get(&[cx, cy, cz])?
+                    .get(kind, local_x, local_z, self.chunk_len)?;
+            Some(cy * len + top as i32)
+        })
+    }
+
+    /// Block at a world position, if its chunk is loaded.
+    pub fn get_block_at(&self, pos: IVec3) -> Option<BlockType> {
+        let (chunk_pos, [x, y, z]) = self.split_pos(pos);
+        self.get_voxel(&chunk_pos, x, y, z)
+    }
+
+    /// Sets the block at a world position, if its chunk is loaded.
+    pub fn set_block_at(&mut self, pos: IVec3, block_type: BlockType) {
+        let (chunk_pos, [x, y, z]) = self.split_pos(pos);
+        self.set_voxel(&chunk_pos, x, y, z, block_type);
+    }
+
+    /// The block's own chunk, followed by any neighbours sharing a face with it, all of which need
+    /// remeshing when the block changes.
+    pub fn touched_chunks(&self, chunk_pos: [i32; 3], local_pos: [usize; 3]) -> Vec<[i32; 3]> {
+        let [cx, cy, cz] = chunk_pos;
+        let [local_x, local_y, local_z] = local_pos;
+
+        let mut chunks = vec![[cx, cy, cz]];
+        if local_x == 0 {
+            chunks.push([cx - 1, cy, cz]);
+        }
+        if local_x == self.chunk_len - 1 {
+            chunks.push([cx + 1, cy, cz]);
+        }
+        if local_y == 0 {
+            chunks.push([cx, cy - 1, cz]);
+        }
+        if local_y == self.chunk_len - 1 {
+            chunks.push([cx, cy + 1, cz]);
+        }
+        if local_z == 0 {
+            chunks.push([cx, cy, cz - 1]);
+        }
+        if local_z == self.chunk_len - 1 {
+            chunks.push([cx, cy, cz + 1]);
+        }
+        chunks
+    }
+
+    /// Splits a world position into its chunk and the position within it.
+    pub fn split_pos(&self, pos: IVec3) -> ([i32; 3], [usize; 3]) {
+        let len = self.chunk_len as i32;
+        let chunk_pos = (pos.div_euclid(IVec3::splat(len))).to_array();
+        let local = pos.rem_euclid(IVec3::splat(len)).as_uvec3();
+        (
+            chunk_pos,
+            [local.x as usize, local.y as usize, local.z as usize],
+        )
+    }
+
+    pub fn world_to_chunk_pos(&self, pos: Vec3) -> [i32; 3] {
+        let chunk_len = self.chunk_len as f32;
+        [
+            (pos.x / chunk_len).floor() as i32,
+            (pos.y / chunk_len).floor() as i32,
+            (pos.z / chunk_len).floor() as i32,
+        ]
     }
 
     pub fn local_pos_to_idx(&self, x: usize, y: usize, z: usize) -> usize {
@@ -78,17 +232,58 @@ impl VoxelWorld {
     }
 }
 
-fn setup(mut commands: Commands) {
-    let encoded_node_tree = "DQADAAAAAAAAQCkAAAAAAD8AAAAAAA==";
-    let node = SafeNode::from_encoded_node_tree(encoded_node_tree).unwrap();
-
-    commands.insert_resource(VoxelWorld {
+fn setup(mut commands: Commands, registry: Res<Registry>) {
+    let mut voxel_world = VoxelWorld {
         world_name: "Test World".to_string(),
-        terrain_noise: node,
-        terrain_frequency: 0.005,
-        terrain_seed: 1338,
+        terrain_seed: 0,
         chunk_len: 32,
         voxels: HashMap::<[i32; 3], Vec<BlockType>>::new(),
         save_timer: Timer::new(Duration::from_secs(5), TimerMode::Repeating),
-    });
+        heightmaps: HashMap::new(),
+        column_chunks: HashMap::new(),
+        heightmap_blocks: HeightmapBlocks::from_registry(&registry),
+        block_collision: BlockCollision::from_registry(&registry),
+    };
+    let save_dir = voxel_world.save_dir();
+
+    let mut metadata = match WorldMetadata::load(&save_dir) {
+        Ok(Some(metadata)) => metadata,
+        Ok(None) => WorldMetadata {
+            game_mode: GameMode::from_args().unwrap_or_default(),
+            ..default()
+        },
+        // Carrying on would overwrite it with a fresh world's settings.
+        Err(e) => panic!("{e}, refusing to open the world"),
+    };
+    voxel_world.terrain_seed = metadata.seed;
+
+    let generator = &metadata.generator;
+    let Some(world_gen_def) = registry.find_world_gen(generator) else {
+        panic!("no worldgen preset named `{generator}`");
+    };
+    let generator = WorldGenerator::from_def(world_gen_def, &registry);
+
+    if metadata.spawn.is_none() {
+        let spawn = find_spawn(
+            &*generator.0,
+            &voxel_world.heightmap_blocks,
+            voxel_world.terrain_seed,
+            voxel_world.chunk_len,
+            registry.get_block_id("air"),
+        );
+        if spawn.is_none() {
+            warn!("couldn't find anywhere safe to spawn, falling back to the origin");
+        }
+        metadata.spawn = Some(spawn.unwrap_or_default().to_array());
+        metadata.save(&save_dir);
+        commands.insert_resource(SpawnNeedsChecking);
+    } else {
+        // Left over from a world that was exited before its spawn got checked.
+        commands.remove_resource::<SpawnNeedsChecking>();
+    }
+
+    commands.insert_resource(generator);
+    commands.insert_resource(PendingBlocks::load(&save_dir));
+    commands.insert_resource(metadata);
+    commands.insert_resource(voxel_world);
 }